@@ -0,0 +1,41 @@
+// Renders a simulation to numbered PNGs without opening a window.
+// usage: headless [steps] [output dir] [width] [height] [--scene file.ron] [--hardware] [--surface] [--screen-space]
+use std::path::PathBuf;
+use wgpu_fluid::render::Headless;
+use wgpu_fluid::sim::Scene;
+
+fn main() -> anyhow::Result<()> {
+	env_logger::init();
+
+	let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+	// the software adapter is the default so this runs on machines without a GPU
+	let hardware = args.iter().any(|a| a == "--hardware");
+	args.retain(|a| a != "--hardware");
+	let surface = args.iter().any(|a| a == "--surface");
+	args.retain(|a| a != "--surface");
+	let screen_space = args.iter().any(|a| a == "--screen-space");
+	args.retain(|a| a != "--screen-space");
+
+	let scene = match args.iter().position(|a| a == "--scene") {
+		Some(i) => {
+			let path = args.get(i + 1).map(PathBuf::from);
+			args.drain(i..(i + 2).min(args.len()));
+			Scene::load(&path.ok_or_else(|| anyhow::anyhow!("--scene needs a file"))?)?
+		}
+		None => Scene::default(),
+	};
+
+	let steps = args.first().map_or(Ok(100), |s| s.parse())?;
+	let dir = PathBuf::from(args.get(1).map_or("frames", |s| s.as_str()));
+	let width = args.get(2).map_or(Ok(800), |s| s.parse())?;
+	let height = args.get(3).map_or(Ok(600), |s| s.parse())?;
+
+	let mut headless = pollster::block_on(Headless::new(&scene, width, height, !hardware))?;
+	if surface {
+		headless.set_surface(Some(scene.surface));
+	}
+	headless.set_screen_space(screen_space);
+	headless.run(steps, &dir)?;
+	log::info!("wrote {steps} frames to {}", dir.display());
+	Ok(())
+}
@@ -0,0 +1,422 @@
+// SPH compute passes, mirrors the CPU solver in particle.rs
+
+struct Params {
+	n: u32,
+	dt: f32,
+	h: f32,
+	rest_density: f32,
+	// 0 ideal gas, 1 tait
+	eos: u32,
+	stiffness: f32,
+	gamma: f32,
+	n_fields: u32,
+	seed: u32,
+	viscosity: f32,
+	// 0 none, 1 speed, 2 density, 3 pressure, 4 density error, 5 vorticity, 6 neighbor count
+	color_by: u32,
+	n_colliders: u32,
+	// of the spatial hash, twice the particle count like `Grid`
+	n_buckets: u32,
+}
+
+// see `FieldGpu` in gpu.rs for the kinds
+struct Field {
+	kind: u32,
+	strength: f32,
+	center: vec4<f32>,
+	vector: vec4<f32>,
+}
+
+// see `ColliderGpu` in gpu.rs, 0 box from a to b, 1 plane with normal a and offset b.x,
+// 2 sphere at a with radius b.x
+struct Collider {
+	kind: u32,
+	restitution: f32,
+	friction: f32,
+	a: vec4<f32>,
+	b: vec4<f32>,
+}
+
+struct Particle {
+	position: vec3<f32>,
+	radius: f32,
+	velocity: vec3<f32>,
+	mass: f32,
+	force: vec3<f32>,
+	density: f32,
+	color: vec3<f32>,
+	pressure: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+
+@group(0) @binding(1)
+var<storage, read_write> particles: array<Particle>;
+
+// ParticleRaw instances, position, radius, color and attribute value
+@group(0) @binding(2)
+var<storage, read_write> instances: array<f32>;
+
+// MAX_FIELDS and MAX_COLLIDERS in gpu.rs
+@group(0) @binding(3)
+var<uniform> fields: array<Field, 16>;
+
+@group(0) @binding(4)
+var<uniform> colliders: array<Collider, 16>;
+
+// the spatial hash of `Grid` in grid.rs, with cells h wide,
+// particles in bucket k are entries[starts[k]..starts[k + 1]]
+@group(0) @binding(5)
+var<storage, read_write> starts: array<atomic<u32>>;
+
+@group(0) @binding(6)
+var<storage, read_write> entries: array<u32>;
+
+const PI: f32 = 3.14159265358979;
+const INSTANCE_STRIDE: u32 = 8u;
+const SCAN_SIZE: u32 = 256u;
+
+fn w_poly6(r_squared: f32) -> f32 {
+	let h = params.h;
+	let d = h * h - r_squared;
+	return (315.0 / (64.0 * PI * pow(h, 9.0))) * d * d * d;
+}
+
+fn w_spiky_grad(r_squared: f32) -> f32 {
+	let h = params.h;
+	let d = h - sqrt(r_squared);
+	return (45.0 / (PI * pow(h, 6.0))) * d * d;
+}
+
+fn w_visc(r_squared: f32) -> f32 {
+	let h = params.h;
+	return (45.0 / (PI * pow(h, 6.0))) * (h - sqrt(r_squared));
+}
+
+fn pressure(density: f32) -> f32 {
+	if params.eos == 1u {
+		let p = params.stiffness * (pow(density / params.rest_density, params.gamma) - 1.0);
+		return max(p, 0.0);
+	}
+	return params.stiffness * (density - params.rest_density);
+}
+
+fn pcg(v: u32) -> u32 {
+	let state = v * 747796405u + 2891336453u;
+	let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+	return (word >> 22u) ^ word;
+}
+
+fn random(seed: ptr<function, u32>) -> f32 {
+	*seed = pcg(*seed);
+	return f32(*seed) / 4294967295.0;
+}
+
+// box-muller
+fn gaussian(seed: ptr<function, u32>) -> f32 {
+	let u1 = max(random(seed), 1e-7);
+	let u2 = random(seed);
+	return sqrt(-2.0 * log(u1)) * cos(2.0 * PI * u2);
+}
+
+fn field_force(f: Field, p: Particle, seed: ptr<function, u32>) -> vec3<f32> {
+	switch f.kind {
+		case 0u: {
+			return p.density * f.vector.xyz;
+		}
+		case 1u: {
+			return f.strength * (f.center.xyz - p.position);
+		}
+		case 2u: {
+			return f.strength * cross(normalize(f.vector.xyz), p.position - f.center.xyz);
+		}
+		case 3u: {
+			return -f.strength * p.velocity;
+		}
+		case 4u: {
+			return f.strength * vec3(gaussian(seed), gaussian(seed), gaussian(seed));
+		}
+		// brush, the radius is in vector.x
+		case 5u: {
+			let d = p.position - f.center.xyz;
+			let r = length(d);
+			if r >= f.vector.x || r < 1e-6 {
+				return vec3(0.0);
+			}
+			return p.density * f.strength * (1.0 - r / f.vector.x) * d / r;
+		}
+		default: {
+			return vec3(0.0);
+		}
+	}
+}
+
+fn cell_of(position: vec3<f32>) -> vec3<i32> {
+	return vec3<i32>(floor(position / params.h));
+}
+
+// same hash as `Grid::bucket`
+fn bucket(cell: vec3<i32>) -> u32 {
+	let h = (cell.x * 92837111) ^ (cell.y * 689287499) ^ (cell.z * 283923481);
+	return bitcast<u32>(abs(h)) % params.n_buckets;
+}
+
+// same as `Collider::respond`
+fn respond(c: Collider, p: ptr<function, Particle>, normal: vec3<f32>, depth: f32) {
+	(*p).position += normal * depth;
+
+	let v_n = dot((*p).velocity, normal);
+	if v_n < 0.0 {
+		var v_t = (*p).velocity - normal * v_n;
+		let v_t_len = length(v_t);
+
+		let impulse = (1.0 + c.restitution) * -v_n;
+		if v_t_len > 0.0 {
+			v_t *= max(1.0 - c.friction * impulse / v_t_len, 0.0);
+		}
+
+		(*p).velocity = v_t - normal * (v_n * c.restitution);
+	}
+}
+
+// same as `Collider::resolve`
+fn resolve(c: Collider, p: ptr<function, Particle>) {
+	let radius = (*p).radius;
+	switch c.kind {
+		case 0u: {
+			for (var axis = 0; axis < 3; axis++) {
+				var normal = vec3(0.0);
+				let x = (*p).position[axis];
+				if x - c.a[axis] < radius {
+					normal[axis] = 1.0;
+					respond(c, p, normal, c.a[axis] + radius - x);
+				} else if c.b[axis] - x < radius {
+					normal[axis] = -1.0;
+					respond(c, p, normal, x + radius - c.b[axis]);
+				}
+			}
+		}
+		case 1u: {
+			let dist = dot(c.a.xyz, (*p).position) - c.b.x;
+			if dist < radius {
+				respond(c, p, c.a.xyz, radius - dist);
+			}
+		}
+		case 2u: {
+			let r = (*p).position - c.a.xyz;
+			let dist = length(r);
+			if dist < c.b.x + radius && dist > 0.0 {
+				respond(c, p, r / dist, c.b.x + radius - dist);
+			}
+		}
+		default: {}
+	}
+}
+
+// the grid is built like `Grid::build`, a counting sort in four passes
+@compute @workgroup_size(64)
+fn clear_grid(@builtin(global_invocation_id) id: vec3<u32>) {
+	if id.x <= params.n_buckets {
+		atomicStore(&starts[id.x], 0u);
+	}
+}
+
+@compute @workgroup_size(64)
+fn count_cells(@builtin(global_invocation_id) id: vec3<u32>) {
+	let i = id.x;
+	if i >= params.n {
+		return;
+	}
+	atomicAdd(&starts[bucket(cell_of(particles[i].position))], 1u);
+}
+
+var<workgroup> sums: array<u32, SCAN_SIZE>;
+
+// inclusive prefix sum over the counts in a single workgroup,
+// each bucket then points one past its end
+@compute @workgroup_size(256)
+fn prefix_sum(@builtin(local_invocation_index) t: u32) {
+	let len = params.n_buckets + 1u;
+	let chunk = (len + SCAN_SIZE - 1u) / SCAN_SIZE;
+	let begin = min(t * chunk, len);
+	let end = min(begin + chunk, len);
+
+	var total = 0u;
+	for (var k = begin; k < end; k++) {
+		total += atomicLoad(&starts[k]);
+	}
+	sums[t] = total;
+	workgroupBarrier();
+
+	for (var stride = 1u; stride < SCAN_SIZE; stride *= 2u) {
+		var v = 0u;
+		if t >= stride {
+			v = sums[t - stride];
+		}
+		workgroupBarrier();
+		sums[t] += v;
+		workgroupBarrier();
+	}
+
+	var running = sums[t] - total;
+	for (var k = begin; k < end; k++) {
+		running += atomicLoad(&starts[k]);
+		atomicStore(&starts[k], running);
+	}
+}
+
+// the order within a bucket is arbitrary
+@compute @workgroup_size(64)
+fn sort_particles(@builtin(global_invocation_id) id: vec3<u32>) {
+	let i = id.x;
+	if i >= params.n {
+		return;
+	}
+	let k = bucket(cell_of(particles[i].position));
+	entries[atomicSub(&starts[k], 1u) - 1u] = i;
+}
+
+@compute @workgroup_size(64)
+fn density(@builtin(global_invocation_id) id: vec3<u32>) {
+	let i = id.x;
+	if i >= params.n {
+		return;
+	}
+	let p_i = particles[i];
+
+	var density = 0.0;
+	let center = cell_of(p_i.position);
+	for (var x = -1; x <= 1; x++) {
+		for (var y = -1; y <= 1; y++) {
+			for (var z = -1; z <= 1; z++) {
+				let cell = center + vec3(x, y, z);
+				let k = bucket(cell);
+				let end = atomicLoad(&starts[k + 1u]);
+				for (var e = atomicLoad(&starts[k]); e < end; e++) {
+					let j = entries[e];
+					// hash collisions
+					if any(cell_of(particles[j].position) != cell) {
+						continue;
+					}
+					let r_ij = particles[j].position - p_i.position;
+					let r_sq = dot(r_ij, r_ij);
+					if r_sq < params.h * params.h {
+						density += particles[j].mass * w_poly6(r_sq);
+					}
+				}
+			}
+		}
+	}
+
+	particles[i].density = density;
+	particles[i].pressure = pressure(density);
+}
+
+@compute @workgroup_size(64)
+fn forces(@builtin(global_invocation_id) id: vec3<u32>) {
+	let i = id.x;
+	if i >= params.n {
+		return;
+	}
+	let p_i = particles[i];
+
+	var f_press = vec3(0.0);
+	var f_visc = vec3(0.0);
+	// for the colormap only
+	var omega = vec3(0.0);
+	var neighbors = 1u;
+	let center = cell_of(p_i.position);
+	for (var x = -1; x <= 1; x++) {
+		for (var y = -1; y <= 1; y++) {
+			for (var z = -1; z <= 1; z++) {
+				let cell = center + vec3(x, y, z);
+				let k = bucket(cell);
+				let end = atomicLoad(&starts[k + 1u]);
+				for (var e = atomicLoad(&starts[k]); e < end; e++) {
+					let j = entries[e];
+					let p_j = particles[j];
+					if i == j || any(cell_of(p_j.position) != cell) {
+						continue;
+					}
+
+					let r_ij = p_j.position - p_i.position;
+					let r_sq = dot(r_ij, r_ij);
+
+					if r_sq < params.h * params.h {
+						neighbors += 1u;
+					}
+					if r_sq < params.h * params.h && r_sq > 0.0 {
+						f_press += -normalize(r_ij)
+							* p_j.mass * (p_i.pressure + p_j.pressure)
+							* w_spiky_grad(r_sq) / (2.0 * p_j.density);
+
+						f_visc += params.viscosity * p_j.mass * (p_j.velocity - p_i.velocity)
+							* w_visc(r_sq) / p_j.density;
+
+						let grad_j = -normalize(r_ij) * w_spiky_grad(r_sq);
+						omega += cross(p_j.velocity - p_i.velocity, grad_j) * p_j.mass / p_j.density;
+					}
+				}
+			}
+		}
+	}
+
+	// the other attributes are written by `integrate`
+	var scalar = 0.0;
+	if params.color_by == 5u {
+		scalar = length(omega);
+	} else if params.color_by == 6u {
+		scalar = f32(neighbors);
+	}
+	instances[i * INSTANCE_STRIDE + 7u] = scalar;
+
+	var seed = pcg(i ^ pcg(params.seed));
+	var f_external = vec3(0.0);
+	for (var k = 0u; k < params.n_fields; k++) {
+		f_external += field_force(fields[k], p_i, &seed);
+	}
+
+	particles[i].force += f_press + f_visc + f_external;
+}
+
+@compute @workgroup_size(64)
+fn integrate(@builtin(global_invocation_id) id: vec3<u32>) {
+	let i = id.x;
+	if i >= params.n {
+		return;
+	}
+	var p = particles[i];
+
+	p.velocity += (p.force / p.density) * params.dt;
+	p.position += p.velocity * params.dt;
+	p.force = vec3(0.0);
+	for (var k = 0u; k < params.n_colliders; k++) {
+		resolve(colliders[k], &p);
+	}
+	particles[i] = p;
+
+	let o = i * INSTANCE_STRIDE;
+	instances[o + 0u] = p.position.x;
+	instances[o + 1u] = p.position.y;
+	instances[o + 2u] = p.position.z;
+	instances[o + 3u] = p.radius;
+	instances[o + 4u] = p.color.x;
+	instances[o + 5u] = p.color.y;
+	instances[o + 6u] = p.color.z;
+	switch params.color_by {
+		case 1u: {
+			instances[o + 7u] = length(p.velocity);
+		}
+		case 2u: {
+			instances[o + 7u] = p.density;
+		}
+		case 3u: {
+			instances[o + 7u] = p.pressure;
+		}
+		case 4u: {
+			instances[o + 7u] = p.density / params.rest_density - 1.0;
+		}
+		default: {}
+	}
+}
@@ -0,0 +1,553 @@
+use crate::render::instances::capacity;
+use crate::render::{ParticleInstances, ParticleRaw};
+use crate::sim::collider::{Collider, Shape};
+use crate::sim::eos::EquationOfState;
+use crate::sim::force::Field;
+use crate::sim::particle::{Attribute, Particle, Particles};
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+const WORKGROUP_SIZE: u32 = 64;
+// fixed in compute.wgsl, the uniform arrays have this size
+const MAX_FIELDS: usize = 16;
+const MAX_COLLIDERS: usize = 16;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+	n: u32,
+	dt: f32,
+	h: f32,
+	rest_density: f32,
+	// 0 ideal gas, 1 tait
+	eos: u32,
+	stiffness: f32,
+	gamma: f32,
+	n_fields: u32,
+	seed: u32,
+	viscosity: f32,
+	// the value written into the instances for the colormap, see `attribute_code`
+	color_by: u32,
+	n_colliders: u32,
+	n_buckets: u32,
+	_pad: [u32; 3],
+}
+
+// matches `Field` in compute.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct FieldGpu {
+	kind: u32,
+	strength: f32,
+	_pad: [f32; 2],
+	center: [f32; 4],
+	vector: [f32; 4],
+}
+
+impl From<Field> for FieldGpu {
+	fn from(field: Field) -> Self {
+		let (kind, strength, center, vector) = match field {
+			Field::Gravity { acceleration } => (0, 0.0, Vector3::zero(), acceleration),
+			Field::Point { center, strength } => (1, strength, center, Vector3::zero()),
+			Field::Vortex {
+				center,
+				axis,
+				strength,
+			} => (2, strength, center, axis),
+			Field::Drag { coefficient } => (3, coefficient, Vector3::zero(), Vector3::zero()),
+			Field::Noise { amplitude } => (4, amplitude, Vector3::zero(), Vector3::zero()),
+			Field::Brush {
+				center,
+				radius,
+				strength,
+			} => (5, strength, center, Vector3::new(radius, 0.0, 0.0)),
+		};
+		Self {
+			kind,
+			strength,
+			_pad: [0.0; 2],
+			center: center.extend(0.0).into(),
+			vector: vector.extend(0.0).into(),
+		}
+	}
+}
+
+// matches `Collider` in compute.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ColliderGpu {
+	kind: u32,
+	restitution: f32,
+	friction: f32,
+	_pad: f32,
+	a: [f32; 4],
+	b: [f32; 4],
+}
+
+impl From<Collider> for ColliderGpu {
+	fn from(collider: Collider) -> Self {
+		// box min and max, plane normal and offset, sphere center and radius
+		let (kind, a, b) = match collider.shape {
+			Shape::Box { min, max } => (0, min.extend(0.0), max.extend(0.0)),
+			Shape::Plane { normal, offset } => (
+				1,
+				normal.normalize().extend(0.0),
+				Vector3::new(offset, 0.0, 0.0).extend(0.0),
+			),
+			Shape::Sphere { center, radius } => (
+				2,
+				center.extend(0.0),
+				Vector3::new(radius, 0.0, 0.0).extend(0.0),
+			),
+		};
+		Self {
+			kind,
+			restitution: collider.restitution,
+			friction: collider.friction,
+			_pad: 0.0,
+			a: a.into(),
+			b: b.into(),
+		}
+	}
+}
+
+// matches `Particle` in compute.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParticleGpu {
+	position: [f32; 3],
+	radius: f32,
+	velocity: [f32; 3],
+	mass: f32,
+	force: [f32; 3],
+	density: f32,
+	color: [f32; 3],
+	pressure: f32,
+}
+
+impl From<Particle> for ParticleGpu {
+	fn from(p: Particle) -> Self {
+		Self {
+			position: p.position.into(),
+			radius: p.radius,
+			velocity: p.velocity.into(),
+			mass: p.mass,
+			force: p.force.into(),
+			density: p.density,
+			color: p.color,
+			pressure: p.pressure,
+		}
+	}
+}
+
+impl From<ParticleGpu> for Particle {
+	fn from(p: ParticleGpu) -> Self {
+		Self {
+			position: Vector3::from(p.position),
+			velocity: Vector3::from(p.velocity),
+			force: Vector3::from(p.force),
+			radius: p.radius,
+			mass: p.mass,
+			density: p.density,
+			pressure: p.pressure,
+			color: p.color,
+		}
+	}
+}
+
+// SPH solver running in compute shaders.
+// Particle state stays on the GPU, the integrate pass writes
+// straight into the instance buffer of `ParticleInstances`.
+// Neighbors come from the same spatial hash as `Grid`, rebuilt every frame
+// with a counting sort, so a frame costs O(n) like on the CPU.
+// Surface tension, adhesion, vorticity confinement and XSPH are only handled
+// by the CPU solvers for now, and this one takes one symplectic Euler step per frame
+// since the velocities never come back to the CPU.
+// Emitters and sinks run on the CPU between steps, `upload` hands back the new particles.
+pub struct GpuSolver {
+	n: u32,
+	// of the particle buffer, in particles
+	capacity: usize,
+	frame: u32,
+	params_buffer: wgpu::Buffer,
+	field_buffer: wgpu::Buffer,
+	collider_buffer: wgpu::Buffer,
+	particle_buffer: wgpu::Buffer,
+	// bucket starts and sorted particle indices of the spatial hash
+	grid_buffers: [wgpu::Buffer; 2],
+	bind_group_layout: wgpu::BindGroupLayout,
+	bind_group: wgpu::BindGroup,
+	clear_grid_pipeline: wgpu::ComputePipeline,
+	count_cells_pipeline: wgpu::ComputePipeline,
+	prefix_sum_pipeline: wgpu::ComputePipeline,
+	sort_particles_pipeline: wgpu::ComputePipeline,
+	density_pipeline: wgpu::ComputePipeline,
+	forces_pipeline: wgpu::ComputePipeline,
+	integrate_pipeline: wgpu::ComputePipeline,
+}
+
+impl GpuSolver {
+	pub fn new(
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		particles: &Particles,
+		instances: &ParticleInstances,
+	) -> Self {
+		let n = particles.particles().len() as u32;
+
+		let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("Sph Params Buffer"),
+			size: std::mem::size_of::<Params>() as u64,
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false,
+		});
+
+		let field_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("Sph Field Buffer"),
+			size: (std::mem::size_of::<FieldGpu>() * MAX_FIELDS) as u64,
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false,
+		});
+
+		let collider_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("Sph Collider Buffer"),
+			size: (std::mem::size_of::<ColliderGpu>() * MAX_COLLIDERS) as u64,
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false,
+		});
+
+		let capacity = capacity(n as usize);
+		let particle_buffer = create_particle_buffer(device, capacity);
+		let grid_buffers = create_grid_buffers(device, capacity);
+
+		let uniform_entry = |binding| wgpu::BindGroupLayoutEntry {
+			binding,
+			visibility: wgpu::ShaderStages::COMPUTE,
+			ty: wgpu::BindingType::Buffer {
+				ty: wgpu::BufferBindingType::Uniform,
+				has_dynamic_offset: false,
+				min_binding_size: None,
+			},
+			count: None,
+		};
+
+		let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+			binding,
+			visibility: wgpu::ShaderStages::COMPUTE,
+			ty: wgpu::BindingType::Buffer {
+				ty: wgpu::BufferBindingType::Storage { read_only },
+				has_dynamic_offset: false,
+				min_binding_size: None,
+			},
+			count: None,
+		};
+
+		let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			entries: &[
+				uniform_entry(0),
+				storage_entry(1, false),
+				storage_entry(2, false),
+				uniform_entry(3),
+				uniform_entry(4),
+				storage_entry(5, false),
+				storage_entry(6, false),
+			],
+			label: Some("sph_bind_group_layout"),
+		});
+
+		let bind_group = create_bind_group(
+			device,
+			&bind_group_layout,
+			[
+				&params_buffer,
+				&particle_buffer,
+				instances.buffer(),
+				&field_buffer,
+				&collider_buffer,
+				&grid_buffers[0],
+				&grid_buffers[1],
+			],
+		);
+
+		let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+			label: Some("compute.wgsl"),
+			source: wgpu::ShaderSource::Wgsl(include_str!("compute.wgsl").into()),
+		});
+
+		let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+			label: Some("Sph Pipeline Layout"),
+			bind_group_layouts: &[&bind_group_layout],
+			push_constant_ranges: &[],
+		});
+
+		let pipeline = |entry_point| {
+			device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+				label: Some(entry_point),
+				layout: Some(&pipeline_layout),
+				module: &shader,
+				entry_point,
+			})
+		};
+
+		let gpu_solver = Self {
+			n,
+			capacity,
+			frame: 0,
+			params_buffer,
+			field_buffer,
+			collider_buffer,
+			clear_grid_pipeline: pipeline("clear_grid"),
+			count_cells_pipeline: pipeline("count_cells"),
+			prefix_sum_pipeline: pipeline("prefix_sum"),
+			sort_particles_pipeline: pipeline("sort_particles"),
+			density_pipeline: pipeline("density"),
+			forces_pipeline: pipeline("forces"),
+			integrate_pipeline: pipeline("integrate"),
+			particle_buffer,
+			grid_buffers,
+			bind_group_layout,
+			bind_group,
+		};
+		gpu_solver.write_particles(queue, particles);
+		gpu_solver
+	}
+
+	pub fn update(
+		&mut self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		particles: &Particles,
+		attribute: Option<Attribute>,
+	) {
+		let fields = particles
+			.fields()
+			.iter()
+			.chain(particles.brush())
+			.filter(|f| f.enabled)
+			.map(|f| FieldGpu::from(f.field))
+			.take(MAX_FIELDS)
+			.collect::<Vec<_>>();
+		if !fields.is_empty() {
+			queue.write_buffer(&self.field_buffer, 0, bytemuck::cast_slice(&fields));
+		}
+
+		let colliders = particles
+			.colliders()
+			.iter()
+			.map(|&c| ColliderGpu::from(c))
+			.take(MAX_COLLIDERS)
+			.collect::<Vec<_>>();
+		if !colliders.is_empty() {
+			queue.write_buffer(&self.collider_buffer, 0, bytemuck::cast_slice(&colliders));
+		}
+
+		let eos = particles.eos();
+		let (kind, gamma) = match eos {
+			EquationOfState::IdealGas { .. } => (0, 1.0),
+			EquationOfState::Tait { gamma, .. } => (1, gamma),
+		};
+
+		let params = Params {
+			n: self.n,
+			dt: particles.timestep().frame_time,
+			h: particles.h(),
+			rest_density: particles.rest_density(),
+			eos: kind,
+			stiffness: eos.stiffness(particles.rest_density()),
+			gamma,
+			n_fields: fields.len() as u32,
+			seed: self.frame,
+			// the compute shader only has the laplacian model
+			viscosity: particles.viscosity().dynamic(particles.rest_density()),
+			color_by: attribute_code(attribute),
+			n_colliders: colliders.len() as u32,
+			n_buckets: n_buckets(self.n as usize),
+			_pad: [0; 3],
+		};
+		queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+		self.frame = self.frame.wrapping_add(1);
+
+		let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+			label: Some("Sph Encoder"),
+		});
+
+		{
+			let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+				label: Some("Sph Pass"),
+			});
+			compute_pass.set_bind_group(0, &self.bind_group, &[]);
+
+			let workgroups = self.n.div_ceil(WORKGROUP_SIZE);
+
+			// the prefix sum runs in a single workgroup
+			compute_pass.set_pipeline(&self.clear_grid_pipeline);
+			compute_pass.dispatch_workgroups(
+				(n_buckets(self.n as usize) + 1).div_ceil(WORKGROUP_SIZE),
+				1,
+				1,
+			);
+			compute_pass.set_pipeline(&self.count_cells_pipeline);
+			compute_pass.dispatch_workgroups(workgroups, 1, 1);
+			compute_pass.set_pipeline(&self.prefix_sum_pipeline);
+			compute_pass.dispatch_workgroups(1, 1, 1);
+
+			for pipeline in [
+				&self.sort_particles_pipeline,
+				&self.density_pipeline,
+				&self.forces_pipeline,
+				&self.integrate_pipeline,
+			] {
+				compute_pass.set_pipeline(pipeline);
+				compute_pass.dispatch_workgroups(workgroups, 1, 1);
+			}
+		}
+
+		queue.submit(std::iter::once(encoder.finish()));
+	}
+
+	// hands edited CPU particles back, the count can change,
+	// `instances` must already have room for them
+	pub fn upload(
+		&mut self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		particles: &Particles,
+		instances: &ParticleInstances,
+	) {
+		let n = particles.particles().len();
+		if n > self.capacity || capacity(n) < self.capacity / 2 {
+			self.capacity = capacity(n);
+			self.particle_buffer = create_particle_buffer(device, self.capacity);
+			self.grid_buffers = create_grid_buffers(device, self.capacity);
+		}
+		if n != self.n as usize {
+			// the instance buffer may have been reallocated as well
+			self.n = n as u32;
+			self.bind_group = create_bind_group(
+				device,
+				&self.bind_group_layout,
+				[
+					&self.params_buffer,
+					&self.particle_buffer,
+					instances.buffer(),
+					&self.field_buffer,
+					&self.collider_buffer,
+					&self.grid_buffers[0],
+					&self.grid_buffers[1],
+				],
+			);
+		}
+		self.write_particles(queue, particles);
+	}
+
+	fn write_particles(&self, queue: &wgpu::Queue, particles: &Particles) {
+		let data = particles
+			.particles()
+			.iter()
+			.map(|&p| ParticleGpu::from(p))
+			.collect::<Vec<_>>();
+		queue.write_buffer(&self.particle_buffer, 0, bytemuck::cast_slice(&data));
+	}
+
+	// blocking readback of the GPU state into the CPU particles
+	pub fn download(&self, device: &wgpu::Device, queue: &wgpu::Queue, particles: &mut Particles) {
+		if self.n == 0 {
+			return;
+		}
+		let size = (self.n as usize * std::mem::size_of::<ParticleGpu>()) as u64;
+		let staging = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("Sph Staging Buffer"),
+			size,
+			usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false,
+		});
+
+		let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+			label: Some("Sph Readback Encoder"),
+		});
+		encoder.copy_buffer_to_buffer(&self.particle_buffer, 0, &staging, 0, size);
+		queue.submit(std::iter::once(encoder.finish()));
+
+		let slice = staging.slice(..);
+		slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+		device.poll(wgpu::Maintain::Wait);
+
+		{
+			let data = slice.get_mapped_range();
+			let data: &[ParticleGpu] = bytemuck::cast_slice(&data);
+			for (p, &gpu) in particles.particles_mut().iter_mut().zip(data) {
+				*p = Particle::from(gpu);
+			}
+		}
+		staging.unmap();
+	}
+}
+
+// 0 writes zeros
+fn attribute_code(attribute: Option<Attribute>) -> u32 {
+	match attribute {
+		None => 0,
+		Some(Attribute::Speed) => 1,
+		Some(Attribute::Density) => 2,
+		Some(Attribute::Pressure) => 3,
+		Some(Attribute::DensityError) => 4,
+		Some(Attribute::Vorticity) => 5,
+		Some(Attribute::Neighbors) => 6,
+	}
+}
+
+// the integrate pass writes raw floats, so the layout has to stay packed
+const _: () = assert!(std::mem::size_of::<ParticleRaw>() == 8 * 4);
+
+fn create_particle_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+	device.create_buffer(&wgpu::BufferDescriptor {
+		label: Some("Sph Particle Buffer"),
+		size: (capacity * std::mem::size_of::<ParticleGpu>()) as u64,
+		usage: wgpu::BufferUsages::STORAGE
+			| wgpu::BufferUsages::COPY_SRC
+			| wgpu::BufferUsages::COPY_DST,
+		mapped_at_creation: false,
+	})
+}
+
+// hash table size for n particles, the same as `Grid::build`
+fn n_buckets(n: usize) -> u32 {
+	(2 * n).max(1) as u32
+}
+
+// bucket starts, one more than the buckets, and the particle indices sorted by bucket
+fn create_grid_buffers(device: &wgpu::Device, capacity: usize) -> [wgpu::Buffer; 2] {
+	[
+		("Sph Grid Start Buffer", n_buckets(capacity) as usize + 1),
+		("Sph Grid Entry Buffer", capacity),
+	]
+	.map(|(label, len)| {
+		device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some(label),
+			size: (len * std::mem::size_of::<u32>()) as u64,
+			usage: wgpu::BufferUsages::STORAGE,
+			mapped_at_creation: false,
+		})
+	})
+}
+
+// params, particles, instances, fields, colliders and the grid, in binding order
+fn create_bind_group(
+	device: &wgpu::Device,
+	layout: &wgpu::BindGroupLayout,
+	buffers: [&wgpu::Buffer; 7],
+) -> wgpu::BindGroup {
+	let entries = buffers
+		.iter()
+		.enumerate()
+		.map(|(binding, buffer)| wgpu::BindGroupEntry {
+			binding: binding as u32,
+			resource: buffer.as_entire_binding(),
+		})
+		.collect::<Vec<_>>();
+	device.create_bind_group(&wgpu::BindGroupDescriptor {
+		layout,
+		entries: &entries,
+		label: Some("sph_bind_group"),
+	})
+}
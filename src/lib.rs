@@ -0,0 +1,14 @@
+// #![deny(clippy::pedantic)]
+// #![allow(clippy::cast_precision_loss)]
+// #![allow(clippy::cast_possible_truncation)]
+// #![allow(clippy::wildcard_imports)]
+
+// Particle based fluid simulation.
+// `sim` only depends on cgmath and rand, the compute shader solver
+// and the renderer need the `render` feature (on by default).
+pub mod sim;
+
+#[cfg(feature = "render")]
+pub mod gpu;
+#[cfg(feature = "render")]
+pub mod render;
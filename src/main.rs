@@ -1,29 +1,29 @@
-// #![deny(clippy::pedantic)]
-// #![allow(clippy::cast_precision_loss)]
-// #![allow(clippy::cast_possible_truncation)]
-// #![allow(clippy::wildcard_imports)]
-
 use winit::{
 	dpi::LogicalPosition,
 	event::*,
 	event_loop::{ControlFlow, EventLoop},
 };
 
-mod camera;
-mod mesh;
-mod particle;
-mod state;
-mod texture;
-
-use state::State;
+use wgpu_fluid::render::{State, Tool};
+use wgpu_fluid::sim::Scene;
 
-fn main() {
-	pollster::block_on(run());
-}
+// F5 saves here, F9 loads it back
+const CHECKPOINT: &str = "checkpoint.bin";
 
-pub async fn run() {
+// usage: wgpu_fluid [scene.ron]
+fn main() -> anyhow::Result<()> {
 	env_logger::init();
 
+	let scene = match std::env::args().nth(1) {
+		Some(path) => Scene::load(path.as_ref())?,
+		None => Scene::default(),
+	};
+
+	pollster::block_on(run(scene));
+	Ok(())
+}
+
+pub async fn run(scene: Scene) {
 	let event_loop = EventLoop::new();
 	let title = env!("CARGO_PKG_NAME");
 	let window = winit::window::WindowBuilder::new()
@@ -32,26 +32,31 @@ pub async fn run() {
 		.build(&event_loop)
 		.unwrap();
 
-	let mut state = State::new(window).await;
+	let mut state = State::new(window, &scene).await;
 
 	// todo: factor this out
 	let mut mouse_pos: cgmath::Point2<f32> = cgmath::Point2::new(0.0, 0.0);
 	let mut mouse_down_left = false;
 	let mut mouse_down_right = false;
 	let mut mouse_down_middle = false;
+	let mut modifiers = ModifiersState::empty();
+	// a modifier-click is acting on the fluid
+	let mut interacting = false;
 
 	event_loop.run(move |event, _, control_flow| {
 		*control_flow = ControlFlow::Poll;
 		match event {
 			Event::MainEventsCleared => state.window().request_redraw(),
+			// the panel gets the first look at every window event
 			Event::WindowEvent {
 				ref event,
 				window_id,
-			} if window_id == state.window().id() => match event {
+			} if window_id == state.window().id() && !state.gui_event(event) => match event {
 				WindowEvent::CursorMoved { position, .. } => {
 					let prev_mouse_pos = mouse_pos;
 					mouse_pos = (position.x as f32, position.y as f32).into();
 					let mouse_delta = mouse_pos - prev_mouse_pos;
+					state.move_cursor(mouse_pos.x, mouse_pos.y);
 
 					if mouse_down_left {
 						state.camera.arcball(mouse_delta);
@@ -78,13 +83,55 @@ pub async fn run() {
 				} => match key {
 					VirtualKeyCode::Escape => *control_flow = ControlFlow::Exit,
 					VirtualKeyCode::R => state.camera.look_at_origin(),
+					VirtualKeyCode::C => state.toggle_gpu(),
+					VirtualKeyCode::E => state.toggle_eos(),
+					VirtualKeyCode::Tab => state.toggle_solver(),
+					VirtualKeyCode::T => state.toggle_adaptive(),
+					VirtualKeyCode::I => state.toggle_integrator(),
+					VirtualKeyCode::M => state.toggle_surface(),
+					VirtualKeyCode::F => state.toggle_screen_space(),
+					VirtualKeyCode::V => state.cycle_attribute(),
+					VirtualKeyCode::B => state.cycle_colormap(),
+					VirtualKeyCode::N => state.toggle_range(),
+					VirtualKeyCode::H => state.toggle_gui(),
+					VirtualKeyCode::Space => state.toggle_pause(),
+					VirtualKeyCode::Period => state.step(),
+					VirtualKeyCode::LBracket => state.slower(),
+					VirtualKeyCode::RBracket => state.faster(),
+					VirtualKeyCode::Back => state.reset(),
+					VirtualKeyCode::F5 => state.save_checkpoint(CHECKPOINT.as_ref()),
+					VirtualKeyCode::F9 => state.load_checkpoint(CHECKPOINT.as_ref()),
+					VirtualKeyCode::Key1 => state.toggle_field(0),
+					VirtualKeyCode::Key2 => state.toggle_field(1),
+					VirtualKeyCode::Key3 => state.toggle_field(2),
+					VirtualKeyCode::Key4 => state.toggle_field(3),
+					VirtualKeyCode::Key5 => state.toggle_field(4),
+					VirtualKeyCode::Key6 => state.toggle_field(5),
+					VirtualKeyCode::Key7 => state.toggle_field(6),
+					VirtualKeyCode::Key8 => state.toggle_field(7),
+					VirtualKeyCode::Key9 => state.toggle_field(8),
 					_ => (),
 				},
-				WindowEvent::MouseInput { state, button, .. } => {
-					let is_down = match state {
+				WindowEvent::ModifiersChanged(m) => modifiers = *m,
+				WindowEvent::MouseInput {
+					state: button_state,
+					button,
+					..
+				} => {
+					let is_down = match button_state {
 						ElementState::Pressed => true,
 						ElementState::Released => false,
 					};
+					if is_down {
+						if let Some(tool) = tool(modifiers, *button) {
+							state.begin_interaction(tool);
+							interacting = true;
+							return;
+						}
+					} else if interacting {
+						state.end_interaction();
+						interacting = false;
+					}
 					match button {
 						MouseButton::Left => mouse_down_left = is_down,
 						MouseButton::Right => mouse_down_right = is_down,
@@ -120,3 +167,15 @@ pub async fn run() {
 		}
 	});
 }
+
+// modifier-clicks act on the fluid instead of the camera
+fn tool(modifiers: ModifiersState, button: MouseButton) -> Option<Tool> {
+	match button {
+		MouseButton::Left if modifiers.shift() => Some(Tool::Push),
+		MouseButton::Right if modifiers.shift() => Some(Tool::Pull),
+		MouseButton::Left if modifiers.ctrl() => Some(Tool::Grab),
+		MouseButton::Left if modifiers.alt() => Some(Tool::Spawn),
+		MouseButton::Right if modifiers.alt() => Some(Tool::Erase),
+		_ => None,
+	}
+}
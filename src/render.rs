@@ -0,0 +1,17 @@
+// Drawing the simulation with wgpu, plus the windowed viewer state.
+pub mod camera;
+pub mod colormap;
+pub mod fluid;
+pub mod gui;
+pub mod headless;
+pub mod instances;
+pub mod lines;
+pub mod mesh;
+pub mod renderer;
+pub mod state;
+pub mod texture;
+
+pub use headless::Headless;
+pub use instances::{ParticleInstances, ParticleRaw};
+pub use renderer::Renderer;
+pub use state::{State, Tool};
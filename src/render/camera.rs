@@ -1,3 +1,5 @@
+use crate::sim::scene::CameraPose;
+use crate::sim::Ray;
 use cgmath::prelude::*;
 // use wgpu::util::DeviceExt;
 
@@ -11,9 +13,16 @@ pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     0.0, 0.0, 0.5, 1.0,
 );
 
+// view and projection are separate for the screen space passes
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct Matrix4([[f32; 4]; 4]);
+pub struct CameraUniform {
+	view_proj: [[f32; 4]; 4],
+	view: [[f32; 4]; 4],
+	proj: [[f32; 4]; 4],
+	// direction toward the sun and its intensity
+	light: [f32; 4],
+}
 
 pub struct Camera {
 	// eye: cgmath::Point3<f32>,
@@ -26,28 +35,33 @@ pub struct Camera {
 	fovy: f32,
 	znear: f32,
 	zfar: f32,
+	// world space direction toward the sun
+	pub light: cgmath::Vector3<f32>,
+	pub light_intensity: f32,
 	pub buffer: wgpu::Buffer,
 }
 
 impl Camera {
-	pub fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
+	pub fn new(device: &wgpu::Device, aspect: f32, pose: &CameraPose) -> Self {
 		let buffer = device.create_buffer(&wgpu::BufferDescriptor {
 			label: Some("Camera Buffer"),
-			size: std::mem::size_of::<Matrix4>() as u64,
+			size: std::mem::size_of::<CameraUniform>() as u64,
 			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
 			mapped_at_creation: false,
 		});
 
 		Camera {
-			yaw: 0.0,
-			pitch: 0.0,
-			dist: 300.0,
-			target: (0.0, 0.0, 0.0).into(),
+			yaw: pose.yaw.to_radians(),
+			pitch: pose.pitch.to_radians().clamp(-YAW_LIMIT, YAW_LIMIT),
+			dist: pose.distance,
+			target: cgmath::Point3::from_vec(pose.target),
 			up: cgmath::Vector3::unit_y(),
-			aspect: config.width as f32 / config.height as f32,
-			fovy: 45.0,
+			aspect,
+			fovy: pose.fovy,
 			znear: 0.1,
 			zfar: 2000.0,
+			light: cgmath::Vector3::new(1.0, 2.0, 1.0).normalize(),
+			light_intensity: 1.0,
 			buffer,
 		}
 	}
@@ -60,10 +74,38 @@ impl Camera {
 		cgmath::Point3::new(px, py, pz)
 	}
 
-	fn get_matrix(&self) -> Matrix4 {
+	fn view_and_proj(&self) -> (cgmath::Matrix4<f32>, cgmath::Matrix4<f32>) {
 		let view = cgmath::Matrix4::look_at_rh(self.eye(), self.target, self.up);
-		let proj = cgmath::perspective(cgmath::Deg(self.fovy), self.aspect, self.znear, self.zfar);
-		Matrix4((OPENGL_TO_WGPU_MATRIX * proj * view).into())
+		let proj = OPENGL_TO_WGPU_MATRIX
+			* cgmath::perspective(cgmath::Deg(self.fovy), self.aspect, self.znear, self.zfar);
+		(view, proj)
+	}
+
+	fn get_uniform(&self) -> CameraUniform {
+		let (view, proj) = self.view_and_proj();
+		CameraUniform {
+			view_proj: (proj * view).into(),
+			view: view.into(),
+			proj: proj.into(),
+			light: self.light.normalize().extend(self.light_intensity).into(),
+		}
+	}
+
+	// unprojects a point in normalized device coordinates, y up, from the near to the far plane
+	pub fn ray(&self, ndc: cgmath::Vector2<f32>) -> Ray {
+		let (view, proj) = self.view_and_proj();
+		let inverse = (proj * view).invert().unwrap();
+		let unproject = |z: f32| {
+			let p = inverse * cgmath::Vector4::new(ndc.x, ndc.y, z, 1.0);
+			p.truncate() / p.w
+		};
+		let near = unproject(0.0);
+		Ray::new(near, unproject(1.0) - near)
+	}
+
+	// from the eye to the point the camera orbits
+	pub fn distance(&self) -> f32 {
+		self.dist
 	}
 
 	pub fn set_aspect(&mut self, aspect: f32) {
@@ -96,6 +138,6 @@ impl Camera {
 	}
 
 	pub fn update(&mut self, queue: &wgpu::Queue) {
-		queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.get_matrix()]));
+		queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.get_uniform()]));
 	}
 }
@@ -0,0 +1,134 @@
+use crate::render::texture::Texture;
+use crate::sim::scene::{Colormap, Visualization};
+
+// matches `Visualization` in colormap.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VisualizationUniform {
+	enabled: u32,
+	colormap: u32,
+	min: f32,
+	max: f32,
+	viewport: [f32; 2],
+	_pad: [f32; 2],
+	labels: [u32; 4],
+}
+
+impl VisualizationUniform {
+	pub fn new(visualization: &Visualization, range: (f32, f32), width: u32, height: u32) -> Self {
+		let (min, max) = range;
+		Self {
+			enabled: visualization.attribute.is_some() as u32,
+			colormap: match visualization.colormap {
+				Colormap::Viridis => 0,
+				Colormap::Coolwarm => 1,
+				Colormap::Turbo => 2,
+			},
+			min,
+			max,
+			viewport: [width as f32, height as f32],
+			_pad: [0.0; 2],
+			labels: [pack(&label(max)), pack(&label(min)), 0, 0],
+		}
+	}
+}
+
+const LABEL_LENGTH: usize = 8;
+
+// shortest text of up to eight characters that shows the value
+fn label(value: f32) -> String {
+	if value == 0.0 || !value.is_finite() {
+		return "0".to_string();
+	}
+	let digits = value.abs().log10().floor() as i32;
+	if (-2..6).contains(&digits) {
+		// three significant digits, as long as it fits
+		let decimals = (2 - digits).max(0) as usize;
+		let text = format!("{value:.decimals$}");
+		if text.len() <= LABEL_LENGTH {
+			return text;
+		}
+	}
+	format!("{value:.1e}")
+}
+
+// glyph codes of legend.wgsl, four bits each, right aligned
+fn pack(text: &str) -> u32 {
+	let codes = text.chars().map(|c| match c {
+		'0'..='9' => c as u32 - '0' as u32 + 1,
+		'.' => 11,
+		'-' => 12,
+		'e' => 13,
+		_ => 0,
+	});
+	let codes = codes.collect::<Vec<_>>();
+	let skip = codes.len().saturating_sub(LABEL_LENGTH);
+	codes[skip..]
+		.iter()
+		.fold(0, |packed, &code| (packed << 4) | code)
+}
+
+// Colormap bar with the range at the right edge of the screen.
+pub struct Legend {
+	pipeline: wgpu::RenderPipeline,
+}
+
+impl Legend {
+	pub fn new(
+		device: &wgpu::Device,
+		layout: &wgpu::PipelineLayout,
+		format: wgpu::TextureFormat,
+	) -> Self {
+		let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+			label: Some("legend.wgsl"),
+			source: wgpu::ShaderSource::Wgsl(
+				concat!(include_str!("colormap.wgsl"), include_str!("legend.wgsl")).into(),
+			),
+		});
+
+		let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some("Legend Pipeline"),
+			layout: Some(layout),
+			vertex: wgpu::VertexState {
+				module: &shader,
+				entry_point: "vs_legend",
+				buffers: &[],
+			},
+			fragment: Some(wgpu::FragmentState {
+				module: &shader,
+				entry_point: "fs_legend",
+				targets: &[Some(wgpu::ColorTargetState {
+					format,
+					blend: Some(wgpu::BlendState::REPLACE),
+					write_mask: wgpu::ColorWrites::ALL,
+				})],
+			}),
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleStrip,
+				..Default::default()
+			},
+			// drawn over everything
+			depth_stencil: Some(wgpu::DepthStencilState {
+				format: Texture::DEPTH_FORMAT,
+				depth_write_enabled: false,
+				depth_compare: wgpu::CompareFunction::Always,
+				stencil: wgpu::StencilState::default(),
+				bias: wgpu::DepthBiasState::default(),
+			}),
+			multisample: wgpu::MultisampleState::default(),
+			multiview: None,
+		});
+
+		Self { pipeline }
+	}
+
+	pub fn draw<'a>(
+		&'a self,
+		render_pass: &mut wgpu::RenderPass<'a>,
+		global_bind_group: &'a wgpu::BindGroup,
+	) {
+		render_pass.set_pipeline(&self.pipeline);
+		render_pass.set_bind_group(0, global_bind_group, &[]);
+		render_pass.draw(0..4, 0..1);
+	}
+}
@@ -0,0 +1,78 @@
+// Colormaps and the color-by-attribute settings, prepended to shader.wgsl and legend.wgsl
+
+struct Visualization {
+	// 0 draws the particle colors
+	enabled: u32,
+	// 0 viridis, 1 coolwarm, 2 turbo
+	colormap: u32,
+	min: f32,
+	max: f32,
+	// target size in pixels
+	viewport: vec2<f32>,
+	_pad: vec2<f32>,
+	// packed legend labels, see colormap.rs
+	labels: vec4<u32>,
+}
+
+@group(0) @binding(1)
+var<uniform> visualization: Visualization;
+
+// polynomial fit by Matt Zucker
+fn viridis(t: f32) -> vec3<f32> {
+	let c0 = vec3(0.2777273272234177, 0.005407344544966578, 0.3340998053353061);
+	let c1 = vec3(0.1050930431085774, 1.404613529898575, 1.384590162594685);
+	let c2 = vec3(-0.3308618287255563, 0.214847559468213, 0.09509516302823659);
+	let c3 = vec3(-4.634230498983486, -5.799100973351585, -19.33244095627987);
+	let c4 = vec3(6.228269936347081, 14.17993336680509, 56.69055260068105);
+	let c5 = vec3(4.776384997670288, -13.74514537774601, -65.35303263337234);
+	let c6 = vec3(-5.435455855934631, 4.645852612178535, 26.3124352495832);
+	return c0 + t * (c1 + t * (c2 + t * (c3 + t * (c4 + t * (c5 + t * c6)))));
+}
+
+// Moreland's diverging map through its quarter points
+fn coolwarm(t: f32) -> vec3<f32> {
+	let x = t * 4.0;
+	let k = min(u32(x), 3u);
+	var colors = array<vec3<f32>, 5>(
+		vec3(0.230, 0.299, 0.754),
+		vec3(0.552, 0.690, 0.996),
+		vec3(0.865, 0.865, 0.865),
+		vec3(0.958, 0.604, 0.482),
+		vec3(0.706, 0.016, 0.150),
+	);
+	return mix(colors[k], colors[k + 1u], x - f32(k));
+}
+
+// polynomial approximation by Google
+fn turbo(t: f32) -> vec3<f32> {
+	let v4 = vec4(1.0, t, t * t, t * t * t);
+	let v2 = v4.zw * v4.z;
+	return vec3(
+		dot(v4, vec4(0.13572138, 4.61539260, -42.66032258, 132.13108234)) + dot(v2, vec2(-152.94239396, 59.28637943)),
+		dot(v4, vec4(0.09140261, 2.19418839, 4.84296658, -14.18503333)) + dot(v2, vec2(4.27729857, 2.82956604)),
+		dot(v4, vec4(0.10667330, 12.64194608, -60.58204836, 110.36276771)) + dot(v2, vec2(-89.90310912, 27.34824973)),
+	);
+}
+
+// linear color of t in 0..1
+fn colormap(t: f32) -> vec3<f32> {
+	let x = saturate(t);
+	var srgb: vec3<f32>;
+	switch visualization.colormap {
+		case 1u: {
+			srgb = coolwarm(x);
+		}
+		case 2u: {
+			srgb = turbo(x);
+		}
+		default: {
+			srgb = viridis(x);
+		}
+	}
+	return pow(saturate(srgb), vec3(2.2));
+}
+
+// position of a value in the current range
+fn normalized(value: f32) -> f32 {
+	return (value - visualization.min) / max(visualization.max - visualization.min, 1e-12);
+}
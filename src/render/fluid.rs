@@ -0,0 +1,445 @@
+use crate::render::instances::{ParticleInstances, ParticleRaw};
+use crate::render::texture::Texture;
+use crate::sim::Particle;
+use wgpu::util::DeviceExt;
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Float;
+const THICKNESS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+// rounds of the separable depth blur
+const BLUR_ITERATIONS: usize = 3;
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct FluidUniform {
+	tint: [f32; 3],
+	absorption: f32,
+	radius: f32,
+	refraction: f32,
+	_pad: [f32; 2],
+}
+
+impl FluidUniform {
+	fn new(particles: &[Particle]) -> Self {
+		let n = particles.len().max(1) as f32;
+		let mut tint = [0.0; 3];
+		let mut radius = 0.0;
+		for p in particles {
+			for (t, c) in tint.iter_mut().zip(p.color) {
+				*t += c / n;
+			}
+			radius += p.radius / n;
+		}
+		Self {
+			tint,
+			// per unit of thickness, a few particles deep is clearly tinted
+			absorption: 0.5 / radius.max(1e-3),
+			radius: radius.max(1e-3),
+			refraction: 0.4,
+			_pad: [0.0; 2],
+		}
+	}
+}
+
+// Screen space fluid rendering: the particles are drawn as sphere sprites into a linear depth
+// and a thickness buffer, the depth is smoothed with a bilateral filter, and the composite
+// reconstructs normals from it to shade a continuous liquid surface with refraction,
+// Fresnel reflection and absorption over the scene behind it.
+pub struct ScreenSpace {
+	params: wgpu::Buffer,
+	blur_layout: wgpu::BindGroupLayout,
+	composite_layout: wgpu::BindGroupLayout,
+	depth_pipeline: wgpu::RenderPipeline,
+	thickness_pipeline: wgpu::RenderPipeline,
+	blur_x_pipeline: wgpu::RenderPipeline,
+	blur_y_pipeline: wgpu::RenderPipeline,
+	composite_pipeline: wgpu::RenderPipeline,
+	targets: Targets,
+}
+
+// everything that depends on the window size
+struct Targets {
+	background: wgpu::TextureView,
+	depth: [wgpu::TextureView; 2],
+	thickness: wgpu::TextureView,
+	// blur_x reads depth[0] into depth[1], blur_y reads it back
+	blur_x: wgpu::BindGroup,
+	blur_y: wgpu::BindGroup,
+	composite: wgpu::BindGroup,
+}
+
+impl ScreenSpace {
+	pub fn new(
+		device: &wgpu::Device,
+		global_bind_group_layout: &wgpu::BindGroupLayout,
+		format: wgpu::TextureFormat,
+		width: u32,
+		height: u32,
+		particles: &[Particle],
+	) -> Self {
+		let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+			label: Some("fluid.wgsl"),
+			source: wgpu::ShaderSource::Wgsl(include_str!("fluid.wgsl").into()),
+		});
+
+		let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Fluid Params Buffer"),
+			contents: bytemuck::bytes_of(&FluidUniform::new(particles)),
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+		});
+
+		let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+			binding,
+			visibility: wgpu::ShaderStages::FRAGMENT,
+			ty: wgpu::BindingType::Texture {
+				sample_type: wgpu::TextureSampleType::Float { filterable: false },
+				view_dimension: wgpu::TextureViewDimension::D2,
+				multisampled: false,
+			},
+			count: None,
+		};
+		let params_entry = wgpu::BindGroupLayoutEntry {
+			binding: 0,
+			visibility: wgpu::ShaderStages::FRAGMENT,
+			ty: wgpu::BindingType::Buffer {
+				ty: wgpu::BufferBindingType::Uniform,
+				has_dynamic_offset: false,
+				min_binding_size: None,
+			},
+			count: None,
+		};
+		let blur_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			entries: &[params_entry, texture_entry(1)],
+			label: Some("blur_bind_group_layout"),
+		});
+		let composite_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			entries: &[
+				params_entry,
+				texture_entry(1),
+				texture_entry(2),
+				texture_entry(3),
+			],
+			label: Some("composite_bind_group_layout"),
+		});
+
+		let sprite_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+			label: Some("Sprite Pipeline Layout"),
+			bind_group_layouts: &[global_bind_group_layout],
+			push_constant_ranges: &[],
+		});
+		let blur_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+			label: Some("Blur Pipeline Layout"),
+			bind_group_layouts: &[global_bind_group_layout, &blur_layout],
+			push_constant_ranges: &[],
+		});
+		let composite_pipeline_layout =
+			device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+				label: Some("Composite Pipeline Layout"),
+				bind_group_layouts: &[global_bind_group_layout, &composite_layout],
+				push_constant_ranges: &[],
+			});
+
+		let sprite_pipeline = |label, entry_point, format, blend, depth_stencil| {
+			device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+				label: Some(label),
+				layout: Some(&sprite_layout),
+				vertex: wgpu::VertexState {
+					module: &shader,
+					entry_point: "vs_sprite",
+					buffers: &[ParticleRaw::LAYOUT],
+				},
+				fragment: Some(wgpu::FragmentState {
+					module: &shader,
+					entry_point,
+					targets: &[Some(wgpu::ColorTargetState {
+						format,
+						blend,
+						write_mask: wgpu::ColorWrites::ALL,
+					})],
+				}),
+				primitive: wgpu::PrimitiveState {
+					topology: wgpu::PrimitiveTopology::TriangleStrip,
+					..Default::default()
+				},
+				depth_stencil,
+				multisample: wgpu::MultisampleState::default(),
+				multiview: None,
+			})
+		};
+		// nearest sphere surface, tested against the scene depth
+		let depth_pipeline = sprite_pipeline(
+			"Fluid Depth Pipeline",
+			"fs_depth",
+			DEPTH_FORMAT,
+			None,
+			Some(wgpu::DepthStencilState {
+				format: Texture::DEPTH_FORMAT,
+				depth_write_enabled: true,
+				depth_compare: wgpu::CompareFunction::Less,
+				stencil: wgpu::StencilState::default(),
+				bias: wgpu::DepthBiasState::default(),
+			}),
+		);
+		let additive = wgpu::BlendComponent {
+			src_factor: wgpu::BlendFactor::One,
+			dst_factor: wgpu::BlendFactor::One,
+			operation: wgpu::BlendOperation::Add,
+		};
+		let thickness_pipeline = sprite_pipeline(
+			"Fluid Thickness Pipeline",
+			"fs_thickness",
+			THICKNESS_FORMAT,
+			Some(wgpu::BlendState {
+				color: additive,
+				alpha: additive,
+			}),
+			None,
+		);
+
+		let fullscreen_pipeline = |label, layout, entry_point, format| {
+			device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+				label: Some(label),
+				layout: Some(layout),
+				vertex: wgpu::VertexState {
+					module: &shader,
+					entry_point: "vs_fullscreen",
+					buffers: &[],
+				},
+				fragment: Some(wgpu::FragmentState {
+					module: &shader,
+					entry_point,
+					targets: &[Some(wgpu::ColorTargetState {
+						format,
+						blend: None,
+						write_mask: wgpu::ColorWrites::ALL,
+					})],
+				}),
+				primitive: wgpu::PrimitiveState::default(),
+				depth_stencil: None,
+				multisample: wgpu::MultisampleState::default(),
+				multiview: None,
+			})
+		};
+		let blur_x_pipeline = fullscreen_pipeline(
+			"Fluid Blur X Pipeline",
+			&blur_pipeline_layout,
+			"fs_blur_x",
+			DEPTH_FORMAT,
+		);
+		let blur_y_pipeline = fullscreen_pipeline(
+			"Fluid Blur Y Pipeline",
+			&blur_pipeline_layout,
+			"fs_blur_y",
+			DEPTH_FORMAT,
+		);
+		let composite_pipeline = fullscreen_pipeline(
+			"Fluid Composite Pipeline",
+			&composite_pipeline_layout,
+			"fs_composite",
+			format,
+		);
+
+		let targets = Targets::new(
+			device,
+			&params,
+			&blur_layout,
+			&composite_layout,
+			format,
+			width,
+			height,
+		);
+
+		Self {
+			params,
+			blur_layout,
+			composite_layout,
+			depth_pipeline,
+			thickness_pipeline,
+			blur_x_pipeline,
+			blur_y_pipeline,
+			composite_pipeline,
+			targets,
+		}
+	}
+
+	pub fn resize(
+		&mut self,
+		device: &wgpu::Device,
+		format: wgpu::TextureFormat,
+		width: u32,
+		height: u32,
+	) {
+		self.targets = Targets::new(
+			device,
+			&self.params,
+			&self.blur_layout,
+			&self.composite_layout,
+			format,
+			width,
+			height,
+		);
+	}
+
+	// the scene without the fluid goes here, the composite refracts it
+	pub fn background(&self) -> &wgpu::TextureView {
+		&self.targets.background
+	}
+
+	// tint and size follow the particles
+	pub fn update(&self, queue: &wgpu::Queue, particles: &[Particle]) {
+		queue.write_buffer(
+			&self.params,
+			0,
+			bytemuck::bytes_of(&FluidUniform::new(particles)),
+		);
+	}
+
+	// `depth_view` must already hold the depth of the background
+	pub fn render(
+		&self,
+		encoder: &mut wgpu::CommandEncoder,
+		view: &wgpu::TextureView,
+		depth_view: &wgpu::TextureView,
+		global_bind_group: &wgpu::BindGroup,
+		instances: &ParticleInstances,
+	) {
+		let targets = &self.targets;
+		{
+			let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+				label: Some("Fluid Depth Pass"),
+				color_attachments: &[Some(clear(&targets.depth[0]))],
+				depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+					view: depth_view,
+					depth_ops: Some(wgpu::Operations {
+						load: wgpu::LoadOp::Load,
+						store: true,
+					}),
+					stencil_ops: None,
+				}),
+			});
+			render_pass.set_pipeline(&self.depth_pipeline);
+			render_pass.set_bind_group(0, global_bind_group, &[]);
+			instances.draw_sprites(&mut render_pass);
+		}
+		{
+			let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+				label: Some("Fluid Thickness Pass"),
+				color_attachments: &[Some(clear(&targets.thickness))],
+				depth_stencil_attachment: None,
+			});
+			render_pass.set_pipeline(&self.thickness_pipeline);
+			render_pass.set_bind_group(0, global_bind_group, &[]);
+			instances.draw_sprites(&mut render_pass);
+		}
+
+		let passes = [
+			(&self.blur_x_pipeline, &targets.blur_x, &targets.depth[1]),
+			(&self.blur_y_pipeline, &targets.blur_y, &targets.depth[0]),
+		];
+		for _ in 0..BLUR_ITERATIONS {
+			for (pipeline, bind_group, target) in passes {
+				let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+					label: Some("Fluid Blur Pass"),
+					color_attachments: &[Some(clear(target))],
+					depth_stencil_attachment: None,
+				});
+				render_pass.set_pipeline(pipeline);
+				render_pass.set_bind_group(0, global_bind_group, &[]);
+				render_pass.set_bind_group(1, bind_group, &[]);
+				render_pass.draw(0..3, 0..1);
+			}
+		}
+
+		let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+			label: Some("Fluid Composite Pass"),
+			color_attachments: &[Some(clear(view))],
+			depth_stencil_attachment: None,
+		});
+		render_pass.set_pipeline(&self.composite_pipeline);
+		render_pass.set_bind_group(0, global_bind_group, &[]);
+		render_pass.set_bind_group(1, &targets.composite, &[]);
+		render_pass.draw(0..3, 0..1);
+	}
+}
+
+fn clear(view: &wgpu::TextureView) -> wgpu::RenderPassColorAttachment<'_> {
+	wgpu::RenderPassColorAttachment {
+		view,
+		resolve_target: None,
+		ops: wgpu::Operations {
+			load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+			store: true,
+		},
+	}
+}
+
+impl Targets {
+	fn new(
+		device: &wgpu::Device,
+		params: &wgpu::Buffer,
+		blur_layout: &wgpu::BindGroupLayout,
+		composite_layout: &wgpu::BindGroupLayout,
+		format: wgpu::TextureFormat,
+		width: u32,
+		height: u32,
+	) -> Self {
+		let target = |label, format| {
+			device
+				.create_texture(&wgpu::TextureDescriptor {
+					label: Some(label),
+					size: wgpu::Extent3d {
+						width,
+						height,
+						depth_or_array_layers: 1,
+					},
+					mip_level_count: 1,
+					sample_count: 1,
+					dimension: wgpu::TextureDimension::D2,
+					format,
+					usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+						| wgpu::TextureUsages::TEXTURE_BINDING,
+					view_formats: &[],
+				})
+				.create_view(&wgpu::TextureViewDescriptor::default())
+		};
+		let background = target("Fluid Background", format);
+		let depth = [
+			target("Fluid Depth", DEPTH_FORMAT),
+			target("Fluid Depth Blurred", DEPTH_FORMAT),
+		];
+		let thickness = target("Fluid Thickness", THICKNESS_FORMAT);
+
+		let bind_group = |label, layout, views: &[&wgpu::TextureView]| {
+			let mut entries = vec![wgpu::BindGroupEntry {
+				binding: 0,
+				resource: params.as_entire_binding(),
+			}];
+			for (i, view) in views.iter().enumerate() {
+				entries.push(wgpu::BindGroupEntry {
+					binding: i as u32 + 1,
+					resource: wgpu::BindingResource::TextureView(view),
+				});
+			}
+			device.create_bind_group(&wgpu::BindGroupDescriptor {
+				layout,
+				entries: &entries,
+				label: Some(label),
+			})
+		};
+		let blur_x = bind_group("blur_x_bind_group", blur_layout, &[&depth[0]]);
+		let blur_y = bind_group("blur_y_bind_group", blur_layout, &[&depth[1]]);
+		let composite = bind_group(
+			"composite_bind_group",
+			composite_layout,
+			&[&depth[0], &thickness, &background],
+		);
+
+		Self {
+			background,
+			depth,
+			thickness,
+			blur_x,
+			blur_y,
+			composite,
+		}
+	}
+}
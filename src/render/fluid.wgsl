@@ -0,0 +1,219 @@
+// Screen space fluid rendering, after van der Laan et al. 2009
+// "Screen space fluid rendering with curvature flow"
+
+struct Camera {
+	view_proj: mat4x4<f32>,
+	view: mat4x4<f32>,
+	proj: mat4x4<f32>,
+	// direction toward the sun, intensity in w
+	light: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct Fluid {
+	// light that is not absorbed
+	tint: vec3<f32>,
+	absorption: f32,
+	// average particle radius
+	radius: f32,
+	// refraction offset in pixels per unit of thickness
+	refraction: f32,
+	_pad: vec2<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> fluid: Fluid;
+
+// Sprites
+
+// drawn larger than the particles so neighbours overlap into a closed surface
+const SPRITE_SCALE: f32 = 1.6;
+
+struct InstanceInput {
+	@location(5) position_radius: vec4<f32>,
+	@location(6) color: vec3<f32>,
+}
+
+struct SpriteOutput {
+	@builtin(position) clip_position: vec4<f32>,
+	@location(0) uv: vec2<f32>,
+	// view space
+	@location(1) center: vec3<f32>,
+	@location(2) radius: f32,
+}
+
+// camera facing quad, drawn as a triangle strip
+@vertex
+fn vs_sprite(
+	@builtin(vertex_index) index: u32,
+	instance: InstanceInput,
+) -> SpriteOutput {
+	let uv = vec2(f32(index & 1u), f32(index >> 1u)) * 2.0 - 1.0;
+	let radius = SPRITE_SCALE * instance.position_radius.w;
+	let center = (camera.view * vec4(instance.position_radius.xyz, 1.0)).xyz;
+
+	var out: SpriteOutput;
+	out.clip_position = camera.proj * vec4(center + vec3(uv * radius, 0.0), 1.0);
+	out.uv = uv;
+	out.center = center;
+	out.radius = radius;
+	return out;
+}
+
+struct DepthOutput {
+	// linear view space depth, 0 where there is no fluid
+	@location(0) depth: f32,
+	@builtin(frag_depth) frag_depth: f32,
+}
+
+@fragment
+fn fs_depth(in: SpriteOutput) -> DepthOutput {
+	let r2 = dot(in.uv, in.uv);
+	if r2 > 1.0 {
+		discard;
+	}
+	let position = in.center + vec3(in.uv, sqrt(1.0 - r2)) * in.radius;
+	let clip = camera.proj * vec4(position, 1.0);
+
+	var out: DepthOutput;
+	out.depth = -position.z;
+	out.frag_depth = clip.z / clip.w;
+	return out;
+}
+
+// summed with additive blending
+@fragment
+fn fs_thickness(in: SpriteOutput) -> @location(0) f32 {
+	let r2 = dot(in.uv, in.uv);
+	if r2 > 1.0 {
+		discard;
+	}
+	return 2.0 * sqrt(1.0 - r2) * in.radius;
+}
+
+// Fullscreen passes
+
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+	let uv = vec2(f32((index << 1u) & 2u), f32(index & 2u));
+	return vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@group(1) @binding(1)
+var depth: texture_2d<f32>;
+
+fn load_depth(coord: vec2<i32>) -> f32 {
+	let size = vec2<i32>(textureDimensions(depth));
+	if any(coord < vec2(0)) || any(coord >= size) {
+		return 0.0;
+	}
+	return textureLoad(depth, coord, 0).r;
+}
+
+// Separable narrow-range filter (Truong and Yuksel 2018). Samples far behind the center are
+// clamped to one particle radius, so the gaps between particles are filled in,
+// and samples far in front belong to another surface and are skipped, keeping silhouettes sharp.
+fn blur(coord: vec2<i32>, direction: vec2<i32>) -> f32 {
+	let z = load_depth(coord);
+	if z <= 0.0 {
+		return 0.0;
+	}
+	// filter width of about two particle radii on screen
+	let height = f32(textureDimensions(depth).y);
+	let pixels = 2.0 * fluid.radius * camera.proj[1][1] * 0.5 * height / z;
+	let n = i32(clamp(pixels, 1.0, 16.0));
+	let sigma = 0.5 * f32(n);
+
+	var sum = 0.0;
+	var weight = 0.0;
+	for (var i = -n; i <= n; i += 1) {
+		let s = load_depth(coord + direction * i);
+		if s > 0.0 && s > z - 2.0 * fluid.radius {
+			let w = exp(-f32(i * i) / (2.0 * sigma * sigma));
+			sum += min(s, z + fluid.radius) * w;
+			weight += w;
+		}
+	}
+	return sum / weight;
+}
+
+@fragment
+fn fs_blur_x(@builtin(position) position: vec4<f32>) -> @location(0) f32 {
+	return blur(vec2<i32>(position.xy), vec2(1, 0));
+}
+
+@fragment
+fn fs_blur_y(@builtin(position) position: vec4<f32>) -> @location(0) f32 {
+	return blur(vec2<i32>(position.xy), vec2(0, 1));
+}
+
+@group(1) @binding(2)
+var thickness: texture_2d<f32>;
+
+// scene behind the fluid
+@group(1) @binding(3)
+var background: texture_2d<f32>;
+
+fn view_position(coord: vec2<i32>, z: f32) -> vec3<f32> {
+	let size = vec2<f32>(textureDimensions(depth));
+	let ndc = (vec2<f32>(coord) + 0.5) / size * vec2(2.0, -2.0) + vec2(-1.0, 1.0);
+	return vec3(ndc.x * z / camera.proj[0][0], ndc.y * z / camera.proj[1][1], -z);
+}
+
+// the smaller of the forward and backward differences, so edges don't smear the normals
+fn difference(coord: vec2<i32>, step: vec2<i32>, position: vec3<f32>) -> vec3<f32> {
+	let za = load_depth(coord + step);
+	let zb = load_depth(coord - step);
+	let forward = view_position(coord + step, za) - position;
+	let backward = position - view_position(coord - step, zb);
+	if za <= 0.0 && zb <= 0.0 {
+		return vec3(f32(step.x), -f32(step.y), 0.0);
+	}
+	if za <= 0.0 || (zb > 0.0 && abs(backward.z) < abs(forward.z)) {
+		return backward;
+	}
+	return forward;
+}
+
+@fragment
+fn fs_composite(@builtin(position) frag_position: vec4<f32>) -> @location(0) vec4<f32> {
+	let coord = vec2<i32>(frag_position.xy);
+	let size = vec2<i32>(textureDimensions(background));
+	let z = load_depth(coord);
+	if z <= 0.0 {
+		return textureLoad(background, coord, 0);
+	}
+
+	let position = view_position(coord, z);
+	let normal = normalize(cross(difference(coord, vec2(0, 1), position), difference(coord, vec2(1, 0), position)));
+	let to_eye = normalize(-position);
+	let view_rotation = mat3x3(camera.view[0].xyz, camera.view[1].xyz, camera.view[2].xyz);
+	let world_normal = transpose(view_rotation) * normal;
+	let light_dir = camera.light.xyz;
+
+	// refraction and absorption
+	let t = textureLoad(thickness, coord, 0).r;
+	let shift = vec2<i32>(normal.xy * vec2(1.0, -1.0) * min(t, 8.0 * fluid.radius) * fluid.refraction);
+	let behind = textureLoad(background, clamp(coord + shift, vec2(0), size - 1), 0).rgb;
+	let transmittance = exp(-(1.0 - fluid.tint) * t * fluid.absorption);
+	let diffuse = 0.25 + 0.6 * camera.light.w * saturate(dot(world_normal, light_dir));
+	let scattered = fluid.tint * (1.0 - exp(-t * fluid.absorption)) * diffuse;
+	let refracted = behind * transmittance + scattered;
+
+	// reflection of a simple sky gradient
+	let reflected = transpose(view_rotation) * reflect(-to_eye, normal);
+	let sky = mix(vec3(0.02, 0.04, 0.08), vec3(0.45, 0.6, 0.8), saturate(0.5 + 0.5 * reflected.y));
+	let fresnel = 0.02 + 0.98 * pow(1.0 - saturate(dot(normal, to_eye)), 5.0);
+
+	let half_dir = normalize(view_rotation * light_dir + to_eye);
+	let specular = pow(saturate(dot(normal, half_dir)), 200.0) * camera.light.w * vec3(1.64, 1.27, 0.99);
+
+	var comp = mix(refracted, sky, fresnel) + specular;
+
+	// same quick tonemapping as the particles
+	comp = smoothstep(vec3(-0.05), vec3(1.3), comp);
+
+	return vec4(comp, 1.0);
+}
@@ -0,0 +1,204 @@
+use std::time::Instant;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+
+pub mod panel;
+
+pub use panel::{Controls, Stats, View};
+
+// egui overlay of the viewer, fed from the winit events and drawn after the frame.
+pub struct Gui {
+	context: egui::Context,
+	renderer: egui_wgpu::Renderer,
+	input: egui::RawInput,
+	// in points
+	pointer: egui::Pos2,
+	pixels_per_point: f32,
+	start: Instant,
+	// tessellated by `run`, drawn by `render`
+	primitives: Vec<egui::ClippedPrimitive>,
+	textures: egui::TexturesDelta,
+	pub visible: bool,
+}
+
+impl Gui {
+	pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, pixels_per_point: f32) -> Self {
+		Self {
+			context: egui::Context::default(),
+			renderer: egui_wgpu::Renderer::new(device, format, None, 1),
+			input: egui::RawInput::default(),
+			pointer: egui::Pos2::ZERO,
+			pixels_per_point,
+			start: Instant::now(),
+			primitives: Vec::new(),
+			textures: egui::TexturesDelta::default(),
+			visible: true,
+		}
+	}
+
+	pub fn set_pixels_per_point(&mut self, pixels_per_point: f32) {
+		self.pixels_per_point = pixels_per_point;
+	}
+
+	// true when egui uses the event, so the viewer shouldn't act on it as well
+	pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+		if !self.visible {
+			return false;
+		}
+		let modifiers = self.input.modifiers;
+		match event {
+			// always passed on, the camera needs the position for its deltas
+			WindowEvent::CursorMoved { position, .. } => {
+				self.pointer = (egui::vec2(position.x as f32, position.y as f32)
+					/ self.pixels_per_point)
+					.to_pos2();
+				self.input
+					.events
+					.push(egui::Event::PointerMoved(self.pointer));
+				false
+			}
+			WindowEvent::CursorLeft { .. } => {
+				self.input.events.push(egui::Event::PointerGone);
+				false
+			}
+			WindowEvent::MouseInput { state, button, .. } => {
+				let button = match button {
+					MouseButton::Left => egui::PointerButton::Primary,
+					MouseButton::Right => egui::PointerButton::Secondary,
+					MouseButton::Middle => egui::PointerButton::Middle,
+					MouseButton::Other(_) => return false,
+				};
+				let pressed = *state == ElementState::Pressed;
+				self.input.events.push(egui::Event::PointerButton {
+					pos: self.pointer,
+					button,
+					pressed,
+					modifiers,
+				});
+				// releases always reach the viewer, drags started outside the panel end there
+				pressed && self.context.wants_pointer_input()
+			}
+			WindowEvent::MouseWheel { delta, .. } => {
+				let delta = match *delta {
+					MouseScrollDelta::LineDelta(x, y) => egui::vec2(x, y) * 24.0,
+					MouseScrollDelta::PixelDelta(p) => {
+						egui::vec2(p.x as f32, p.y as f32) / self.pixels_per_point
+					}
+				};
+				self.input.events.push(egui::Event::Scroll(delta));
+				self.context.wants_pointer_input()
+			}
+			WindowEvent::ReceivedCharacter(c) if !c.is_control() => {
+				self.input.events.push(egui::Event::Text(c.to_string()));
+				self.context.wants_keyboard_input()
+			}
+			WindowEvent::KeyboardInput { input, .. } => {
+				if let Some(key) = input.virtual_keycode.and_then(key) {
+					self.input.events.push(egui::Event::Key {
+						key,
+						pressed: input.state == ElementState::Pressed,
+						repeat: false,
+						modifiers,
+					});
+				}
+				self.context.wants_keyboard_input()
+			}
+			WindowEvent::ModifiersChanged(state) => {
+				self.input.modifiers = egui::Modifiers {
+					alt: state.alt(),
+					ctrl: state.ctrl(),
+					shift: state.shift(),
+					mac_cmd: cfg!(target_os = "macos") && state.logo(),
+					command: if cfg!(target_os = "macos") {
+						state.logo()
+					} else {
+						state.ctrl()
+					},
+				};
+				false
+			}
+			WindowEvent::Focused(focused) => {
+				self.input.focused = *focused;
+				false
+			}
+			_ => false,
+		}
+	}
+
+	// lays out the ui for this frame, `size` in pixels
+	pub fn run(&mut self, size: [u32; 2], ui: impl FnOnce(&egui::Context)) {
+		let mut input = std::mem::take(&mut self.input);
+		// pending modifiers and focus carry over to the next frame
+		self.input.modifiers = input.modifiers;
+		self.input.focused = input.focused;
+
+		input.screen_rect = Some(egui::Rect::from_min_size(
+			egui::Pos2::ZERO,
+			egui::vec2(size[0] as f32, size[1] as f32) / self.pixels_per_point,
+		));
+		input.pixels_per_point = Some(self.pixels_per_point);
+		input.time = Some(self.start.elapsed().as_secs_f64());
+
+		let output = self.context.run(input, ui);
+		self.textures.append(output.textures_delta);
+		self.primitives = self.context.tessellate(output.shapes);
+	}
+
+	pub fn render(
+		&mut self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		encoder: &mut wgpu::CommandEncoder,
+		view: &wgpu::TextureView,
+		size: [u32; 2],
+	) {
+		let textures = std::mem::take(&mut self.textures);
+		let screen = egui_wgpu::renderer::ScreenDescriptor {
+			size_in_pixels: size,
+			pixels_per_point: self.pixels_per_point,
+		};
+		for (id, image_delta) in &textures.set {
+			self.renderer
+				.update_texture(device, queue, *id, image_delta);
+		}
+		// the panel has no paint callbacks, so there are no command buffers to submit
+		self.renderer
+			.update_buffers(device, queue, encoder, &self.primitives, &screen);
+		{
+			let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+				label: Some("Gui Pass"),
+				color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+					view,
+					resolve_target: None,
+					ops: wgpu::Operations {
+						load: wgpu::LoadOp::Load,
+						store: true,
+					},
+				})],
+				depth_stencil_attachment: None,
+			});
+			self.renderer
+				.render(&mut render_pass, &self.primitives, &screen);
+		}
+		for id in &textures.free {
+			self.renderer.free_texture(id);
+		}
+	}
+}
+
+// keys for editing text fields, the characters themselves come as `ReceivedCharacter`
+fn key(key: VirtualKeyCode) -> Option<egui::Key> {
+	Some(match key {
+		VirtualKeyCode::Back => egui::Key::Backspace,
+		VirtualKeyCode::Delete => egui::Key::Delete,
+		VirtualKeyCode::Return => egui::Key::Enter,
+		VirtualKeyCode::Tab => egui::Key::Tab,
+		VirtualKeyCode::Escape => egui::Key::Escape,
+		VirtualKeyCode::Left => egui::Key::ArrowLeft,
+		VirtualKeyCode::Right => egui::Key::ArrowRight,
+		VirtualKeyCode::Up => egui::Key::ArrowUp,
+		VirtualKeyCode::Down => egui::Key::ArrowDown,
+		VirtualKeyCode::Home => egui::Key::Home,
+		VirtualKeyCode::End => egui::Key::End,
+		_ => return None,
+	})
+}
@@ -0,0 +1,307 @@
+use crate::sim::clock::TIME_SCALES;
+use crate::sim::eos::EquationOfState;
+use crate::sim::particle::{VelocityParams, Viscosity};
+use crate::sim::scene::{Colormap, Visualization};
+use crate::sim::{Attribute, Integrator, Solver};
+use std::time::Duration;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum View {
+	Particles,
+	// marching cubes surface
+	Surface,
+	ScreenSpace,
+}
+
+// Everything the panel edits, read from the viewer before the ui runs and applied after.
+#[derive(Debug, Copy, Clone)]
+pub struct Controls {
+	pub paused: bool,
+	pub time_scale: f32,
+	// actions, set for one frame when their button is clicked
+	pub step: bool,
+	pub reset: bool,
+	pub swap_eos: bool,
+
+	pub gpu: bool,
+	pub solver: Solver,
+	pub integrator: Integrator,
+	pub eos: EquationOfState,
+	pub rest_density: f32,
+	pub viscosity: Viscosity,
+	pub velocity: VelocityParams,
+	pub h: f32,
+	pub frame_time: f32,
+	pub adaptive: bool,
+
+	pub view: View,
+	pub visualization: Visualization,
+	// in degrees
+	pub light_azimuth: f32,
+	pub light_elevation: f32,
+	pub light_intensity: f32,
+}
+
+// Read only numbers shown at the top of the panel.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Stats {
+	pub fps: f32,
+	pub particles: usize,
+	pub step_time: Duration,
+	pub max_density_error: f32,
+	pub time: f64,
+	pub substeps: usize,
+	// current colormap range
+	pub range: (f32, f32),
+}
+
+const SOLVERS: [(Solver, &str); 3] = [
+	(Solver::StateEquation, "state equation"),
+	(Solver::Pbf, "position based"),
+	(Solver::Dfsph, "divergence-free"),
+];
+
+const INTEGRATORS: [Integrator; 3] = [
+	Integrator::SymplecticEuler,
+	Integrator::Leapfrog,
+	Integrator::Rk4,
+];
+
+const COLORMAPS: [Colormap; 3] = [Colormap::Viridis, Colormap::Coolwarm, Colormap::Turbo];
+
+pub fn show(context: &egui::Context, controls: &mut Controls, stats: &Stats) {
+	egui::Window::new("wgpu_fluid")
+		.default_pos([10.0, 10.0])
+		.resizable(false)
+		.show(context, |ui| {
+			stats_grid(ui, stats);
+			ui.separator();
+
+			ui.horizontal(|ui| {
+				let label = if controls.paused { "play" } else { "pause" };
+				if ui.button(label).clicked() {
+					controls.paused = !controls.paused;
+				}
+				if ui
+					.add_enabled(controls.paused, egui::Button::new("step"))
+					.clicked()
+				{
+					controls.step = true;
+				}
+				if ui.button("reset").clicked() {
+					controls.reset = true;
+				}
+				egui::ComboBox::from_id_source("time scale")
+					.width(60.0)
+					.selected_text(format!("{}x", controls.time_scale))
+					.show_ui(ui, |ui| {
+						for scale in TIME_SCALES {
+							ui.selectable_value(
+								&mut controls.time_scale,
+								scale,
+								format!("{scale}x"),
+							);
+						}
+					});
+			});
+
+			egui::CollapsingHeader::new("solver")
+				.default_open(true)
+				.show(ui, |ui| solver(ui, controls));
+			egui::CollapsingHeader::new("view")
+				.default_open(true)
+				.show(ui, |ui| view(ui, controls, stats));
+		});
+}
+
+fn stats_grid(ui: &mut egui::Ui, stats: &Stats) {
+	egui::Grid::new("stats").num_columns(2).show(ui, |ui| {
+		ui.label("fps");
+		ui.label(format!("{:.0}", stats.fps));
+		ui.end_row();
+		ui.label("particles");
+		ui.label(stats.particles.to_string());
+		ui.end_row();
+		ui.label("step time");
+		ui.label(format!(
+			"{:.2} ms ({} substeps)",
+			stats.step_time.as_secs_f64() * 1000.0,
+			stats.substeps
+		));
+		ui.end_row();
+		ui.label("max density error");
+		ui.label(format!("{:.2}%", stats.max_density_error * 100.0));
+		ui.end_row();
+		ui.label("simulated time");
+		ui.label(format!("{:.1}", stats.time));
+		ui.end_row();
+	});
+}
+
+fn solver(ui: &mut egui::Ui, controls: &mut Controls) {
+	ui.checkbox(&mut controls.gpu, "compute shader solver");
+
+	// the GPU solver always runs the state equation with symplectic Euler
+	ui.add_enabled_ui(!controls.gpu, |ui| {
+		let name = |solver| SOLVERS.iter().find(|(s, _)| *s == solver).unwrap().1;
+		egui::ComboBox::from_label("solver")
+			.selected_text(name(controls.solver))
+			.show_ui(ui, |ui| {
+				for (solver, name) in SOLVERS {
+					ui.selectable_value(&mut controls.solver, solver, name);
+				}
+			});
+		egui::ComboBox::from_label("integrator")
+			.selected_text(controls.integrator.name())
+			.show_ui(ui, |ui| {
+				for integrator in INTEGRATORS {
+					ui.selectable_value(&mut controls.integrator, integrator, integrator.name());
+				}
+			});
+	});
+
+	ui.horizontal(|ui| {
+		ui.label(format!("equation of state: {}", controls.eos.name()));
+		if ui.button("swap").clicked() {
+			controls.swap_eos = true;
+		}
+	});
+	match &mut controls.eos {
+		EquationOfState::IdealGas { gas_const } => {
+			ui.add(
+				egui::Slider::new(gas_const, 1.0..=500.0)
+					.logarithmic(true)
+					.text("gas constant"),
+			);
+		}
+		EquationOfState::Tait {
+			speed_of_sound,
+			gamma,
+		} => {
+			ui.add(
+				egui::Slider::new(speed_of_sound, 1.0..=100.0)
+					.logarithmic(true)
+					.text("speed of sound"),
+			);
+			ui.add(egui::Slider::new(gamma, 1.0..=7.0).text("gamma"));
+		}
+	}
+	ui.add(
+		egui::Slider::new(&mut controls.rest_density, 1e-4..=1e-2)
+			.logarithmic(true)
+			.text("rest density"),
+	);
+	viscosity(ui, &mut controls.viscosity, controls.gpu);
+	ui.add_enabled_ui(!controls.gpu, |ui| {
+		ui.add(
+			egui::Slider::new(&mut controls.velocity.vorticity, 0.0..=0.01)
+				.logarithmic(true)
+				.text("vorticity confinement"),
+		);
+		ui.add(
+			egui::Slider::new(&mut controls.velocity.xsph, 0.0..=0.5)
+				.logarithmic(true)
+				.text("xsph smoothing"),
+		);
+	});
+	ui.add(egui::Slider::new(&mut controls.h, 5.0..=40.0).text("smoothing length"));
+	ui.add(
+		egui::Slider::new(&mut controls.frame_time, 0.01..=2.0)
+			.logarithmic(true)
+			.text("time per step"),
+	);
+	ui.checkbox(&mut controls.adaptive, "adaptive substeps");
+}
+
+fn viscosity(ui: &mut egui::Ui, viscosity: &mut Viscosity, gpu: bool) {
+	// the GPU solver only has the laplacian model
+	ui.add_enabled_ui(!gpu, |ui| {
+		egui::ComboBox::from_label("viscosity")
+			.selected_text(viscosity.name())
+			.show_ui(ui, |ui| {
+				for model in Viscosity::ALL {
+					let selected = model.name() == viscosity.name();
+					if ui.selectable_label(selected, model.name()).clicked() && !selected {
+						*viscosity = model;
+					}
+				}
+			});
+	});
+	match viscosity {
+		Viscosity::Laplacian { dynamic } => {
+			ui.add(
+				egui::Slider::new(dynamic, 0.0..=1.0)
+					.logarithmic(true)
+					.text("dynamic viscosity"),
+			);
+		}
+		Viscosity::Artificial { alpha, beta } => {
+			ui.add(egui::Slider::new(alpha, 0.0..=1.0).text("alpha"));
+			ui.add(egui::Slider::new(beta, 0.0..=2.0).text("beta"));
+		}
+		Viscosity::Morris { kinematic } => {
+			ui.add(
+				egui::Slider::new(kinematic, 0.0..=100.0)
+					.logarithmic(true)
+					.text("kinematic viscosity"),
+			);
+		}
+		Viscosity::Implicit { kinematic, .. } => {
+			ui.add(
+				egui::Slider::new(kinematic, 0.0..=100_000.0)
+					.logarithmic(true)
+					.text("kinematic viscosity"),
+			);
+		}
+	}
+}
+
+fn view(ui: &mut egui::Ui, controls: &mut Controls, stats: &Stats) {
+	ui.horizontal(|ui| {
+		ui.radio_value(&mut controls.view, View::Particles, "particles");
+		ui.radio_value(&mut controls.view, View::Surface, "surface");
+		ui.radio_value(&mut controls.view, View::ScreenSpace, "screen space");
+	});
+
+	let visualization = &mut controls.visualization;
+	let attribute = visualization.attribute;
+	egui::ComboBox::from_label("color by")
+		.selected_text(attribute.map_or("particle color", Attribute::name))
+		.show_ui(ui, |ui| {
+			ui.selectable_value(&mut visualization.attribute, None, "particle color");
+			for a in Attribute::ALL {
+				ui.selectable_value(&mut visualization.attribute, Some(a), a.name());
+			}
+		});
+	if visualization.attribute != attribute {
+		// a fixed range of one attribute means nothing for the next
+		visualization.range = None;
+	}
+	ui.add_enabled_ui(visualization.attribute.is_some(), |ui| {
+		egui::ComboBox::from_label("colormap")
+			.selected_text(visualization.colormap.name())
+			.show_ui(ui, |ui| {
+				for colormap in COLORMAPS {
+					ui.selectable_value(&mut visualization.colormap, colormap, colormap.name());
+				}
+			});
+		let mut fixed = visualization.range.is_some();
+		ui.checkbox(&mut fixed, "fixed range");
+		match (fixed, &mut visualization.range) {
+			(true, Some((min, max))) => {
+				ui.horizontal(|ui| {
+					let speed = 0.01 * (*max - *min).abs().max(1e-6);
+					ui.add(egui::DragValue::new(min).speed(speed));
+					ui.label("to");
+					ui.add(egui::DragValue::new(max).speed(speed));
+				});
+			}
+			(true, None) => visualization.range = Some(stats.range),
+			(false, _) => visualization.range = None,
+		}
+	});
+
+	ui.add(egui::Slider::new(&mut controls.light_azimuth, -180.0..=180.0).text("light azimuth"));
+	ui.add(egui::Slider::new(&mut controls.light_elevation, 0.0..=90.0).text("light elevation"));
+	ui.add(egui::Slider::new(&mut controls.light_intensity, 0.0..=3.0).text("light intensity"));
+}
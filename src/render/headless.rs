@@ -0,0 +1,202 @@
+use crate::render::camera::Camera;
+use crate::render::renderer::Renderer;
+use crate::sim::export::Exporter;
+use crate::sim::surface::Surface;
+use crate::sim::{Particles, Scene};
+use anyhow::Context;
+use std::path::Path;
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+// Offscreen rendering without a window or surface, for CI and batch runs.
+// Frames are drawn into a texture and read back as RGBA8.
+pub struct Headless {
+	device: wgpu::Device,
+	queue: wgpu::Queue,
+	pub camera: Camera,
+	pub particles: Particles,
+	renderer: Renderer,
+	exporter: Option<Exporter>,
+	target: wgpu::Texture,
+	readback: wgpu::Buffer,
+	width: u32,
+	height: u32,
+	// rows of the readback buffer are padded to COPY_BYTES_PER_ROW_ALIGNMENT
+	padded_bytes_per_row: u32,
+}
+
+impl Headless {
+	// `force_fallback` asks for a software adapter, so this also runs without a GPU or display
+	pub async fn new(
+		scene: &Scene,
+		width: u32,
+		height: u32,
+		force_fallback: bool,
+	) -> anyhow::Result<Self> {
+		let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+			backends: wgpu::Backends::all(),
+			dx12_shader_compiler: wgpu::Dx12Compiler::default(),
+		});
+
+		let adapter = instance
+			.request_adapter(&wgpu::RequestAdapterOptions {
+				power_preference: wgpu::PowerPreference::HighPerformance,
+				compatible_surface: None,
+				force_fallback_adapter: force_fallback,
+			})
+			.await
+			.context("no suitable adapter found")?;
+
+		log::info!("headless adapter: {:?}", adapter.get_info());
+
+		let (device, queue) = adapter
+			.request_device(
+				&wgpu::DeviceDescriptor {
+					label: None,
+					features: wgpu::Features::empty(),
+					limits: wgpu::Limits::downlevel_defaults(),
+				},
+				None,
+			)
+			.await?;
+
+		let target = device.create_texture(&wgpu::TextureDescriptor {
+			label: Some("Headless Target"),
+			size: wgpu::Extent3d {
+				width,
+				height,
+				depth_or_array_layers: 1,
+			},
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: wgpu::TextureDimension::D2,
+			format: FORMAT,
+			usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+			view_formats: &[],
+		});
+
+		let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+		let padded_bytes_per_row = (width * 4).div_ceil(align) * align;
+		let readback = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("Headless Readback Buffer"),
+			size: (padded_bytes_per_row * height) as u64,
+			usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false,
+		});
+
+		let camera = Camera::new(&device, width as f32 / height as f32, &scene.camera);
+		let particles = Particles::new(scene);
+		let mut renderer =
+			Renderer::new(&device, &queue, FORMAT, width, height, &camera, &particles);
+		renderer.set_visualization(&queue, scene.visualization);
+		let exporter = scene.export.clone().map(Exporter::new).transpose()?;
+
+		Ok(Self {
+			device,
+			queue,
+			camera,
+			particles,
+			renderer,
+			exporter,
+			target,
+			readback,
+			width,
+			height,
+			padded_bytes_per_row,
+		})
+	}
+
+	pub fn step(&mut self) -> anyhow::Result<()> {
+		self.particles.update();
+		self.renderer
+			.update(&self.device, &self.queue, &mut self.particles);
+		self.renderer
+			.update_surface(&self.device, &self.queue, &self.particles);
+		if let Some(exporter) = &mut self.exporter {
+			exporter.frame(&self.particles)?;
+		}
+		Ok(())
+	}
+
+	// draw the reconstructed liquid surface instead of the particles
+	pub fn set_surface(&mut self, surface: Option<Surface>) {
+		self.renderer.set_surface(surface);
+		self.renderer
+			.update_surface(&self.device, &self.queue, &self.particles);
+	}
+
+	pub fn set_screen_space(&mut self, on: bool) {
+		self.renderer
+			.set_screen_space(&self.device, &self.particles, on);
+	}
+
+	// draws the current state and returns tightly packed RGBA8 rows
+	pub fn render(&mut self) -> Vec<u8> {
+		self.camera.update(&self.queue);
+
+		let view = self
+			.target
+			.create_view(&wgpu::TextureViewDescriptor::default());
+		let mut encoder = self
+			.device
+			.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+				label: Some("Headless Encoder"),
+			});
+
+		self.renderer.render(&mut encoder, &view);
+
+		encoder.copy_texture_to_buffer(
+			self.target.as_image_copy(),
+			wgpu::ImageCopyBuffer {
+				buffer: &self.readback,
+				layout: wgpu::ImageDataLayout {
+					offset: 0,
+					bytes_per_row: Some(self.padded_bytes_per_row),
+					rows_per_image: Some(self.height),
+				},
+			},
+			wgpu::Extent3d {
+				width: self.width,
+				height: self.height,
+				depth_or_array_layers: 1,
+			},
+		);
+		self.queue.submit(std::iter::once(encoder.finish()));
+
+		let slice = self.readback.slice(..);
+		slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+		self.device.poll(wgpu::Maintain::Wait);
+
+		let row_bytes = (self.width * 4) as usize;
+		let pixels = {
+			let data = slice.get_mapped_range();
+			data.chunks(self.padded_bytes_per_row as usize)
+				.flat_map(|row| &row[..row_bytes])
+				.copied()
+				.collect::<Vec<_>>()
+		};
+		self.readback.unmap();
+		pixels
+	}
+
+	pub fn save_png(&mut self, path: &Path) -> anyhow::Result<()> {
+		let pixels = self.render();
+		let file = std::fs::File::create(path)
+			.with_context(|| format!("could not create {}", path.display()))?;
+		let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), self.width, self.height);
+		encoder.set_color(png::ColorType::Rgba);
+		encoder.set_depth(png::BitDepth::Eight);
+		encoder.write_header()?.write_image_data(&pixels)?;
+		Ok(())
+	}
+
+	// steps the simulation and writes frame_00000.png, frame_00001.png, ... into `dir`
+	pub fn run(&mut self, steps: usize, dir: &Path) -> anyhow::Result<()> {
+		std::fs::create_dir_all(dir)?;
+		for frame in 0..steps {
+			self.step()?;
+			self.save_png(&dir.join(format!("frame_{frame:05}.png")))?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,125 @@
+use crate::sim::particle::Particle;
+use cgmath::Vector3;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ParticleRaw {
+	position: [f32; 3],
+	radius: f32,
+	color: [f32; 3],
+	// attribute value for the colormap
+	scalar: f32,
+}
+
+impl ParticleRaw {
+	pub const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+		array_stride: std::mem::size_of::<ParticleRaw>() as wgpu::BufferAddress,
+		step_mode: wgpu::VertexStepMode::Instance,
+		attributes: &wgpu::vertex_attr_array![5 => Float32x4, 6 => Float32x3, 7 => Float32],
+	};
+
+	pub fn new(position: Vector3<f32>, radius: f32, color: [f32; 3]) -> Self {
+		Self {
+			position: position.into(),
+			radius,
+			color,
+			scalar: 0.0,
+		}
+	}
+}
+
+impl From<&Particle> for ParticleRaw {
+	fn from(p: &Particle) -> Self {
+		Self::new(p.position, p.radius, p.color)
+	}
+}
+
+// smallest buffer, in particles
+const MIN_CAPACITY: usize = 1024;
+
+// room for `n` particles and some growth, always a power of two
+pub fn capacity(n: usize) -> usize {
+	n.next_power_of_two().max(MIN_CAPACITY)
+}
+
+// One camera facing quad per particle, the shaders turn it into a sphere.
+// The buffer grows and shrinks with the particle count.
+pub struct ParticleInstances {
+	buffer: wgpu::Buffer,
+	capacity: usize,
+	num_instances: u32,
+}
+
+impl ParticleInstances {
+	pub fn new(device: &wgpu::Device, n: usize) -> Self {
+		let capacity = capacity(n);
+		Self {
+			buffer: create_buffer(device, capacity),
+			capacity,
+			num_instances: 0,
+		}
+	}
+
+	pub fn buffer(&self) -> &wgpu::Buffer {
+		&self.buffer
+	}
+
+	// `scalars` are the attribute values, one per particle
+	pub fn update(
+		&mut self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		particles: &[Particle],
+		scalars: Option<&[f32]>,
+	) {
+		self.reserve(device, particles.len());
+		let instance_data = particles
+			.iter()
+			.enumerate()
+			.map(|(i, p)| ParticleRaw {
+				scalar: scalars.map_or(0.0, |s| s[i]),
+				..ParticleRaw::from(p)
+			})
+			.collect::<Vec<_>>();
+		queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&instance_data));
+		self.num_instances = particles.len() as u32;
+	}
+
+	// reallocates when `n` particles don't fit or use less than a quarter of the buffer,
+	// the contents are not kept, a new buffer also needs a new GPU solver bind group
+	pub fn reserve(&mut self, device: &wgpu::Device, n: usize) {
+		if n > self.capacity || capacity(n) < self.capacity / 2 {
+			self.capacity = capacity(n);
+			self.buffer = create_buffer(device, self.capacity);
+			log::debug!("particle buffer resized to {}", self.capacity);
+		}
+	}
+
+	pub fn draw<'a>(
+		&'a self,
+		render_pass: &mut wgpu::RenderPass<'a>,
+		impostor_pipeline: &'a wgpu::RenderPipeline,
+		global_bind_group: &'a wgpu::BindGroup,
+	) {
+		render_pass.set_pipeline(impostor_pipeline);
+		render_pass.set_bind_group(0, global_bind_group, &[]);
+		self.draw_sprites(render_pass);
+	}
+
+	// four vertex triangle strip per instance, the pipeline is set by the caller
+	pub fn draw_sprites<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+		render_pass.set_vertex_buffer(0, self.buffer.slice(..));
+		render_pass.draw(0..4, 0..self.num_instances);
+	}
+}
+
+fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+	device.create_buffer(&wgpu::BufferDescriptor {
+		label: Some("Particle Buffer"),
+		size: (std::mem::size_of::<ParticleRaw>() * capacity) as u64,
+		usage: wgpu::BufferUsages::VERTEX
+			| wgpu::BufferUsages::STORAGE
+			| wgpu::BufferUsages::COPY_DST,
+		mapped_at_creation: false,
+	})
+}
@@ -0,0 +1,95 @@
+// Colormap legend at the right edge of the screen, with the range as labels
+
+// in pixels
+const BAR_WIDTH: f32 = 16.0;
+const MARGIN: f32 = 24.0;
+// 3x5 glyphs
+const SCALE: f32 = 2.0;
+const ADVANCE: f32 = 8.0;
+const LABEL_LENGTH: f32 = 8.0;
+
+struct Layout {
+	bar_min: vec2<f32>,
+	bar_max: vec2<f32>,
+	// right edge of the labels
+	label_x: f32,
+}
+
+fn legend_layout() -> Layout {
+	let size = visualization.viewport;
+	var out: Layout;
+	out.bar_min = vec2(size.x - MARGIN - BAR_WIDTH, 0.2 * size.y);
+	out.bar_max = vec2(size.x - MARGIN, 0.8 * size.y);
+	out.label_x = out.bar_min.x - 3.0 * SCALE;
+	return out;
+}
+
+// one quad around the bar and its labels, drawn as a triangle strip
+@vertex
+fn vs_legend(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+	let l = legend_layout();
+	let lo = vec2(l.label_x - LABEL_LENGTH * ADVANCE, l.bar_min.y - 5.0 * SCALE) - 2.0;
+	let hi = l.bar_max + vec2(2.0, 5.0 * SCALE + 2.0);
+	let corner = mix(lo, hi, vec2(f32(index & 1u), f32(index >> 1u)));
+	let ndc = corner / visualization.viewport * vec2(2.0, -2.0) + vec2(-1.0, 1.0);
+	return vec4(ndc, 0.0, 1.0);
+}
+
+// rows from the top, left column in the highest bit
+fn glyph(code: u32) -> u32 {
+	switch code {
+		case 1u: { return 0x7b6fu; }
+		case 2u: { return 0x2c97u; }
+		case 3u: { return 0x73e7u; }
+		case 4u: { return 0x73cfu; }
+		case 5u: { return 0x5bc9u; }
+		case 6u: { return 0x79cfu; }
+		case 7u: { return 0x79efu; }
+		case 8u: { return 0x7249u; }
+		case 9u: { return 0x7befu; }
+		case 10u: { return 0x7bcfu; }
+		// .
+		case 11u: { return 0x2u; }
+		// -
+		case 12u: { return 0x1c0u; }
+		// e
+		case 13u: { return 0x5e3u; }
+		default: { return 0u; }
+	}
+}
+
+// whether `position` hits the right aligned label, vertically centered on `y`
+fn label(text: u32, y: f32, right: f32, position: vec2<f32>) -> bool {
+	let p = (position - vec2(right - LABEL_LENGTH * ADVANCE, y - 2.5 * SCALE)) / SCALE;
+	let advance = ADVANCE / SCALE;
+	if p.x < 0.0 || p.y < 0.0 || p.y >= 5.0 || p.x >= LABEL_LENGTH * advance {
+		return false;
+	}
+	let k = u32(p.x / advance);
+	let column = u32(p.x - f32(k) * advance);
+	let row = u32(p.y);
+	if column > 2u {
+		return false;
+	}
+	let code = (text >> (28u - 4u * k)) & 15u;
+	return ((glyph(code) >> ((4u - row) * 3u + 2u - column)) & 1u) != 0u;
+}
+
+@fragment
+fn fs_legend(@builtin(position) frag_position: vec4<f32>) -> @location(0) vec4<f32> {
+	let l = legend_layout();
+	let p = frag_position.xy;
+
+	if all(p >= l.bar_min) && all(p <= l.bar_max) {
+		let t = (l.bar_max.y - p.y) / (l.bar_max.y - l.bar_min.y);
+		return vec4(colormap(t), 1.0);
+	}
+	// outline
+	if all(p >= l.bar_min - 1.0) && all(p <= l.bar_max + 1.0) {
+		return vec4(vec3(0.6), 1.0);
+	}
+	if label(visualization.labels.x, l.bar_min.y, l.label_x, p) || label(visualization.labels.y, l.bar_max.y, l.label_x, p) {
+		return vec4(vec3(0.8), 1.0);
+	}
+	discard;
+}
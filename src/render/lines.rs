@@ -0,0 +1,62 @@
+use cgmath::Vector3;
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LineVertex {
+	pub position: [f32; 3],
+	pub color: [f32; 3],
+}
+
+impl LineVertex {
+	pub const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+		array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+		step_mode: wgpu::VertexStepMode::Vertex,
+		attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
+	};
+}
+
+// static wireframe geometry, drawn as a line list
+pub struct Lines {
+	vertex_buffer: wgpu::Buffer,
+	num_vertices: u32,
+}
+
+impl Lines {
+	pub fn new(device: &wgpu::Device, segments: &[[Vector3<f32>; 2]], color: [f32; 3]) -> Self {
+		let vertices = segments
+			.iter()
+			.flatten()
+			.map(|&p| LineVertex {
+				position: p.into(),
+				color,
+			})
+			.collect::<Vec<_>>();
+
+		let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Lines Vertex Buffer"),
+			contents: bytemuck::cast_slice(&vertices),
+			usage: wgpu::BufferUsages::VERTEX,
+		});
+
+		Self {
+			vertex_buffer,
+			num_vertices: vertices.len() as u32,
+		}
+	}
+
+	pub fn draw<'a>(
+		&'a self,
+		render_pass: &mut wgpu::RenderPass<'a>,
+		line_pipeline: &'a wgpu::RenderPipeline,
+		global_bind_group: &'a wgpu::BindGroup,
+	) {
+		if self.num_vertices == 0 {
+			return;
+		}
+		render_pass.set_pipeline(line_pipeline);
+		render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+		render_pass.set_bind_group(0, global_bind_group, &[]);
+		render_pass.draw(0..self.num_vertices, 0..1);
+	}
+}
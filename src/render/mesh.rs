@@ -1,3 +1,4 @@
+use crate::sim::surface::TriangleMesh;
 use std::ops::Range;
 use wgpu::util::DeviceExt;
 
@@ -58,24 +59,51 @@ impl Mesh {
 			})
 			.collect::<Vec<_>>();
 
+		Self::new(device, file_name, &vertices, &mesh.indices)
+	}
+
+	pub fn new(
+		device: &wgpu::Device,
+		label: &str,
+		vertices: &[Vertex],
+		indices: &[u32],
+	) -> anyhow::Result<Self> {
 		let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-			label: Some(&format!("{file_name} Vertex Buffer")),
-			contents: bytemuck::cast_slice(&vertices),
+			label: Some(&format!("{label} Vertex Buffer")),
+			contents: bytemuck::cast_slice(vertices),
 			usage: wgpu::BufferUsages::VERTEX,
 		});
 		let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-			label: Some(&format!("{file_name} Index Buffer")),
-			contents: bytemuck::cast_slice(&mesh.indices),
+			label: Some(&format!("{label} Index Buffer")),
+			contents: bytemuck::cast_slice(indices),
 			usage: wgpu::BufferUsages::INDEX,
 		});
 
 		Ok(Mesh {
 			vertex_buffer,
 			index_buffer,
-			num_elements: u32::try_from(mesh.indices.len())?,
+			num_elements: u32::try_from(indices.len())?,
 		})
 	}
 
+	// the liquid surface, drawn through a single identity instance
+	pub fn from_triangles(
+		device: &wgpu::Device,
+		label: &str,
+		mesh: &TriangleMesh,
+	) -> anyhow::Result<Self> {
+		let vertices = mesh
+			.positions
+			.iter()
+			.zip(&mesh.normals)
+			.map(|(p, n)| Vertex {
+				position: (*p).into(),
+				normal: (*n).into(),
+			})
+			.collect::<Vec<_>>();
+		Self::new(device, label, &vertices, &mesh.indices)
+	}
+
 	// pub fn draw<'a>(
 	// 	&'a self,
 	// 	render_pass: &mut wgpu::RenderPass<'a>,
@@ -0,0 +1,479 @@
+use crate::render::camera::Camera;
+use crate::render::colormap::{Legend, VisualizationUniform};
+use crate::render::fluid::ScreenSpace;
+use crate::render::instances::{ParticleInstances, ParticleRaw};
+use crate::render::lines::{LineVertex, Lines};
+use crate::render::mesh::{Mesh, Vertex};
+use crate::render::texture::Texture;
+use crate::sim::scene::Visualization;
+use crate::sim::surface::Surface;
+use crate::sim::Particles;
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use wgpu::util::DeviceExt;
+
+// Draws the particles and colliders into any color target,
+// shared by the window and the headless mode.
+pub struct Renderer {
+	depth_texture: Texture,
+	format: wgpu::TextureFormat,
+	width: u32,
+	height: u32,
+	global_bind_group_layout: wgpu::BindGroupLayout,
+	global_bind_group: wgpu::BindGroup,
+	// lit meshes, only the surface for now
+	render_pipeline: wgpu::RenderPipeline,
+	impostor_pipeline: wgpu::RenderPipeline,
+	line_pipeline: wgpu::RenderPipeline,
+	instances: ParticleInstances,
+	collider_lines: Lines,
+	// draw the reconstructed liquid surface instead of the particles
+	surface: Option<Surface>,
+	surface_mesh: Option<Mesh>,
+	// the surface goes through the particle pipeline as one untransformed instance
+	surface_instance: wgpu::Buffer,
+	// created when switching to screen space fluid rendering
+	screen_space: Option<ScreenSpace>,
+	// coloring particles by an attribute
+	visualization: Visualization,
+	// range of the last attribute values, used when the visualization has none
+	auto_range: (f32, f32),
+	visualization_buffer: wgpu::Buffer,
+	legend: Legend,
+}
+
+impl Renderer {
+	pub fn new(
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		format: wgpu::TextureFormat,
+		width: u32,
+		height: u32,
+		camera: &Camera,
+		particles: &Particles,
+	) -> Self {
+		let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+			label: Some("shader.wgsl"),
+			source: wgpu::ShaderSource::Wgsl(
+				concat!(include_str!("colormap.wgsl"), include_str!("shader.wgsl")).into(),
+			),
+		});
+
+		// setup
+		let depth_texture = Texture::create_depth_texture(device, width, height, "depth_texture");
+		let mut instances = ParticleInstances::new(device, particles.particles().len());
+		instances.update(device, queue, particles.particles(), None);
+		let collider_segments = particles
+			.colliders()
+			.iter()
+			.flat_map(|c| c.lines())
+			.collect::<Vec<_>>();
+		let collider_lines = Lines::new(device, &collider_segments, [0.3, 0.35, 0.4]);
+
+		// pipeline
+		let global_bind_group_layout =
+			device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+				entries: &[
+					wgpu::BindGroupLayoutEntry {
+						binding: 0,
+						visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+						ty: wgpu::BindingType::Buffer {
+							ty: wgpu::BufferBindingType::Uniform,
+							has_dynamic_offset: false,
+							min_binding_size: None,
+						},
+						count: None,
+					},
+					wgpu::BindGroupLayoutEntry {
+						binding: 1,
+						visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+						ty: wgpu::BindingType::Buffer {
+							ty: wgpu::BufferBindingType::Uniform,
+							has_dynamic_offset: false,
+							min_binding_size: None,
+						},
+						count: None,
+					},
+				],
+				label: Some("global_bind_group_layout"),
+			});
+
+		let visualization = Visualization::default();
+		let auto_range = (0.0, 1.0);
+		let visualization_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Visualization Buffer"),
+			contents: bytemuck::bytes_of(&VisualizationUniform::new(
+				&visualization,
+				auto_range,
+				width,
+				height,
+			)),
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+		});
+
+		let global_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			layout: &global_bind_group_layout,
+			entries: &[
+				wgpu::BindGroupEntry {
+					binding: 0,
+					resource: camera.buffer.as_entire_binding(),
+				},
+				wgpu::BindGroupEntry {
+					binding: 1,
+					resource: visualization_buffer.as_entire_binding(),
+				},
+			],
+			label: Some("global_bind_group"),
+		});
+
+		let render_pipeline_layout =
+			device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+				label: Some("Render Pipeline Layout"),
+				bind_group_layouts: &[&global_bind_group_layout],
+				push_constant_ranges: &[],
+			});
+
+		let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some("Render Pipeline"),
+			layout: Some(&render_pipeline_layout),
+			vertex: wgpu::VertexState {
+				module: &shader,
+				entry_point: "vs_main",
+				buffers: &[Vertex::LAYOUT, ParticleRaw::LAYOUT],
+			},
+			fragment: Some(wgpu::FragmentState {
+				module: &shader,
+				entry_point: "fs_main",
+				targets: &[Some(wgpu::ColorTargetState {
+					format,
+					blend: Some(wgpu::BlendState {
+						color: wgpu::BlendComponent::REPLACE,
+						alpha: wgpu::BlendComponent::REPLACE,
+					}),
+					write_mask: wgpu::ColorWrites::ALL,
+				})],
+			}),
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleList,
+				strip_index_format: None,
+				front_face: wgpu::FrontFace::Ccw,
+				cull_mode: Some(wgpu::Face::Back),
+				polygon_mode: wgpu::PolygonMode::Fill,
+				unclipped_depth: false,
+				conservative: false,
+			},
+			depth_stencil: Some(wgpu::DepthStencilState {
+				format: Texture::DEPTH_FORMAT,
+				depth_write_enabled: true,
+				depth_compare: wgpu::CompareFunction::Less,
+				stencil: wgpu::StencilState::default(),
+				bias: wgpu::DepthBiasState::default(),
+			}),
+			multisample: wgpu::MultisampleState {
+				count: 1,
+				mask: !0,
+				alpha_to_coverage_enabled: false,
+			},
+			multiview: None,
+		});
+
+		let impostor_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some("Impostor Pipeline"),
+			layout: Some(&render_pipeline_layout),
+			vertex: wgpu::VertexState {
+				module: &shader,
+				entry_point: "vs_impostor",
+				buffers: &[ParticleRaw::LAYOUT],
+			},
+			fragment: Some(wgpu::FragmentState {
+				module: &shader,
+				entry_point: "fs_impostor",
+				targets: &[Some(wgpu::ColorTargetState {
+					format,
+					blend: Some(wgpu::BlendState::REPLACE),
+					write_mask: wgpu::ColorWrites::ALL,
+				})],
+			}),
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleStrip,
+				..Default::default()
+			},
+			depth_stencil: Some(wgpu::DepthStencilState {
+				format: Texture::DEPTH_FORMAT,
+				depth_write_enabled: true,
+				depth_compare: wgpu::CompareFunction::Less,
+				stencil: wgpu::StencilState::default(),
+				bias: wgpu::DepthBiasState::default(),
+			}),
+			multisample: wgpu::MultisampleState::default(),
+			multiview: None,
+		});
+
+		let line_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some("Line Pipeline"),
+			layout: Some(&render_pipeline_layout),
+			vertex: wgpu::VertexState {
+				module: &shader,
+				entry_point: "vs_line",
+				buffers: &[LineVertex::LAYOUT],
+			},
+			fragment: Some(wgpu::FragmentState {
+				module: &shader,
+				entry_point: "fs_line",
+				targets: &[Some(wgpu::ColorTargetState {
+					format,
+					blend: Some(wgpu::BlendState::REPLACE),
+					write_mask: wgpu::ColorWrites::ALL,
+				})],
+			}),
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::LineList,
+				..Default::default()
+			},
+			depth_stencil: Some(wgpu::DepthStencilState {
+				format: Texture::DEPTH_FORMAT,
+				depth_write_enabled: true,
+				depth_compare: wgpu::CompareFunction::Less,
+				stencil: wgpu::StencilState::default(),
+				bias: wgpu::DepthBiasState::default(),
+			}),
+			multisample: wgpu::MultisampleState::default(),
+			multiview: None,
+		});
+
+		let legend = Legend::new(device, &render_pipeline_layout, format);
+
+		let surface_instance = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Surface Instance Buffer"),
+			contents: bytemuck::bytes_of(&ParticleRaw::new(Vector3::zero(), 1.0, [0.2, 0.45, 0.9])),
+			usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+		});
+
+		Self {
+			depth_texture,
+			format,
+			width,
+			height,
+			global_bind_group_layout,
+			global_bind_group,
+			render_pipeline,
+			impostor_pipeline,
+			line_pipeline,
+			instances,
+			collider_lines,
+			surface: None,
+			surface_mesh: None,
+			surface_instance,
+			screen_space: None,
+			visualization,
+			auto_range,
+			visualization_buffer,
+			legend,
+		}
+	}
+
+	pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+		self.depth_texture = Texture::create_depth_texture(device, width, height, "depth_texture");
+		self.width = width;
+		self.height = height;
+		if let Some(screen_space) = &mut self.screen_space {
+			screen_space.resize(device, self.format, width, height);
+		}
+	}
+
+	pub fn instances(&self) -> &ParticleInstances {
+		&self.instances
+	}
+
+	// copy the CPU particles into the instance buffer
+	pub fn update(
+		&mut self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		particles: &mut Particles,
+	) {
+		let scalars = self
+			.visualization
+			.attribute
+			.map(|attribute| particles.attribute(attribute));
+		self.instances
+			.update(device, queue, particles.particles(), scalars.as_deref());
+		if let Some(scalars) = &scalars {
+			self.auto_range = range(scalars);
+		}
+		self.write_visualization(queue);
+		if let Some(screen_space) = &self.screen_space {
+			screen_space.update(queue, particles.particles());
+		}
+	}
+
+	// the GPU solver writes the attribute values itself,
+	// only the automatic range needs the (downloaded) CPU particles
+	pub fn update_range(&mut self, queue: &wgpu::Queue, particles: &mut Particles) {
+		if self.visualization.range.is_none() {
+			if let Some(attribute) = self.visualization.attribute {
+				self.auto_range = range(&particles.attribute(attribute));
+			}
+		}
+		self.write_visualization(queue);
+	}
+
+	// whether the colormap range is taken from the current attribute values
+	pub fn follows_range(&self) -> bool {
+		self.visualization.attribute.is_some() && self.visualization.range.is_none()
+	}
+
+	pub fn visualization(&self) -> Visualization {
+		self.visualization
+	}
+
+	pub fn set_visualization(&mut self, queue: &wgpu::Queue, visualization: Visualization) {
+		self.visualization = visualization;
+		self.write_visualization(queue);
+	}
+
+	// the values mapped to the ends of the colormap
+	pub fn range(&self) -> (f32, f32) {
+		self.visualization.range.unwrap_or(self.auto_range)
+	}
+
+	fn write_visualization(&self, queue: &wgpu::Queue) {
+		let uniform =
+			VisualizationUniform::new(&self.visualization, self.range(), self.width, self.height);
+		queue.write_buffer(&self.visualization_buffer, 0, bytemuck::bytes_of(&uniform));
+	}
+
+	pub fn screen_space(&self) -> bool {
+		self.screen_space.is_some()
+	}
+
+	pub fn set_screen_space(&mut self, device: &wgpu::Device, particles: &Particles, on: bool) {
+		self.screen_space = on.then(|| {
+			ScreenSpace::new(
+				device,
+				&self.global_bind_group_layout,
+				self.format,
+				self.width,
+				self.height,
+				particles.particles(),
+			)
+		});
+	}
+
+	pub fn surface(&self) -> Option<Surface> {
+		self.surface
+	}
+
+	pub fn set_surface(&mut self, surface: Option<Surface>) {
+		self.surface = surface;
+		self.surface_mesh = None;
+	}
+
+	// rebuild the surface mesh on the CPU, does nothing while particles are drawn
+	pub fn update_surface(
+		&mut self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		particles: &Particles,
+	) {
+		let Some(surface) = self.surface else {
+			return;
+		};
+		let particles = particles.particles();
+		let mesh = surface.reconstruct(particles);
+		self.surface_mesh = if mesh.indices.is_empty() {
+			None
+		} else {
+			Mesh::from_triangles(device, "Surface", &mesh).ok()
+		};
+
+		// average particle color
+		let mut color = [0.0; 3];
+		for p in particles {
+			for (c, x) in color.iter_mut().zip(p.color) {
+				*c += x / particles.len() as f32;
+			}
+		}
+		queue.write_buffer(
+			&self.surface_instance,
+			0,
+			bytemuck::bytes_of(&ParticleRaw::new(Vector3::zero(), 1.0, color)),
+		);
+	}
+
+	pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+		// the screen space fluid is composited over the rest of the scene
+		let target = match &self.screen_space {
+			Some(screen_space) => screen_space.background(),
+			None => view,
+		};
+		let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+			label: Some("Render Pass"),
+			color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+				view: target,
+				resolve_target: None,
+				ops: wgpu::Operations {
+					load: wgpu::LoadOp::Clear(wgpu::Color {
+						// these are linear
+						r: 0.006,
+						g: 0.02,
+						b: 0.05,
+						a: 1.0,
+					}),
+					store: true,
+				},
+			})],
+			depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+				view: &self.depth_texture.view,
+				depth_ops: Some(wgpu::Operations {
+					load: wgpu::LoadOp::Clear(1.0),
+					store: true,
+				}),
+				stencil_ops: None,
+			}),
+		});
+
+		let draw_particles = self.surface.is_none() && self.screen_space.is_none();
+		if draw_particles {
+			self.instances.draw(
+				&mut render_pass,
+				&self.impostor_pipeline,
+				&self.global_bind_group,
+			);
+		} else if let Some(mesh) = &self.surface_mesh {
+			render_pass.set_pipeline(&self.render_pipeline);
+			render_pass.set_vertex_buffer(1, self.surface_instance.slice(..));
+			mesh.draw_instanced(&mut render_pass, 0..1, &self.global_bind_group);
+		}
+		self.collider_lines.draw(
+			&mut render_pass,
+			&self.line_pipeline,
+			&self.global_bind_group,
+		);
+		if draw_particles && self.visualization.attribute.is_some() {
+			self.legend.draw(&mut render_pass, &self.global_bind_group);
+		}
+		drop(render_pass);
+
+		if let Some(screen_space) = &self.screen_space {
+			screen_space.render(
+				encoder,
+				view,
+				&self.depth_texture.view,
+				&self.global_bind_group,
+				&self.instances,
+			);
+		}
+	}
+}
+
+// smallest and largest value, (0, 1) when there are none
+fn range(values: &[f32]) -> (f32, f32) {
+	let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+	let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+	if min <= max {
+		(min, max)
+	} else {
+		(0.0, 1.0)
+	}
+}
@@ -0,0 +1,163 @@
+// Vertex shader
+
+fn hash3(n: f32 ) -> vec3<f32> { return fract(sin(vec3(n,n+1.0,n+2.0))*43758.5453123); }
+
+
+struct Camera {
+	view_proj: mat4x4<f32>,
+	view: mat4x4<f32>,
+	proj: mat4x4<f32>,
+	// direction toward the sun, intensity in w
+	light: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct VertexInput {
+	@location(0) position: vec3<f32>,
+	@location(1) normal: vec3<f32>,
+}
+
+struct InstanceInput {
+	@location(5) position_radius: vec4<f32>,
+	@location(6) color: vec3<f32>,
+	// attribute value for the colormap
+	@location(7) scalar: f32,
+}
+
+struct VertexOutput {
+	@builtin(position) clip_position: vec4<f32>,
+	@location(0) color: vec3<f32>,
+	@location(1) normal: vec3<f32>,
+}
+
+@vertex
+fn vs_main(
+	model: VertexInput,
+	instance: InstanceInput,
+) -> VertexOutput {
+	let world_position = instance.position_radius.xyz + model.position * instance.position_radius.w;
+	var out: VertexOutput;
+	out.clip_position = camera.view_proj * vec4<f32>(world_position, 1.0);
+	out.normal = model.normal;
+	out.color = instance.color;
+	return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+	return shade(in.normal, in.color);
+}
+
+// world space normal
+fn shade(normal: vec3<f32>, color: vec3<f32>) -> vec4<f32> {
+	let light_dir = camera.light.xyz;
+
+	let ind_dir = normalize(light_dir * vec3(-1.0, -0.5, -1.0));
+
+	let sun: f32 = saturate(dot(light_dir, normal));
+	let sky: f32 = saturate(0.5 + 0.5 * normal.y);
+	let ind: f32 = saturate(dot(normal, ind_dir));
+	var lighting: vec3<f32> = sun * camera.light.w * vec3(1.64, 1.27, 0.99);
+	lighting += sky * vec3(0.16, 0.20, 0.28);
+	lighting += ind * vec3(0.60, 0.42, 0.32);
+
+	var comp = lighting * max(vec3(0.05), color) ;
+
+	// quick tonemapping
+	// should not be here actually
+	comp = smoothstep(vec3(-0.05), vec3(1.3), comp);
+
+	return vec4(comp, 1.0);
+}
+
+// Sphere impostors
+
+struct ImpostorOutput {
+	@builtin(position) clip_position: vec4<f32>,
+	@location(0) color: vec3<f32>,
+	// view space
+	@location(1) quad_position: vec3<f32>,
+	@location(2) center: vec3<f32>,
+	@location(3) radius: f32,
+}
+
+// camera facing quad through the sphere center, drawn as a triangle strip
+@vertex
+fn vs_impostor(
+	@builtin(vertex_index) index: u32,
+	instance: InstanceInput,
+) -> ImpostorOutput {
+	let uv = vec2(f32(index & 1u), f32(index >> 1u)) * 2.0 - 1.0;
+	let radius = instance.position_radius.w;
+	let center = (camera.view * vec4(instance.position_radius.xyz, 1.0)).xyz;
+
+	// under perspective the silhouette is wider than the sphere where it crosses the center plane
+	let d2 = dot(center, center);
+	let size = radius * sqrt(d2 / max(d2 - radius * radius, 1e-6));
+	let quad_position = center + vec3(uv * size, 0.0);
+
+	var out: ImpostorOutput;
+	out.clip_position = camera.proj * vec4(quad_position, 1.0);
+	out.color = instance.color;
+	if visualization.enabled != 0u {
+		out.color = colormap(normalized(instance.scalar));
+	}
+	out.quad_position = quad_position;
+	out.center = center;
+	out.radius = radius;
+	return out;
+}
+
+struct ImpostorFragment {
+	@location(0) color: vec4<f32>,
+	@builtin(frag_depth) depth: f32,
+}
+
+@fragment
+fn fs_impostor(in: ImpostorOutput) -> ImpostorFragment {
+	// ray from the eye through the quad against the sphere
+	let dir = normalize(in.quad_position);
+	let b = dot(dir, in.center);
+	let discriminant = b * b - dot(in.center, in.center) + in.radius * in.radius;
+	if discriminant < 0.0 {
+		discard;
+	}
+	let hit = dir * (b - sqrt(discriminant));
+	let normal = (hit - in.center) / in.radius;
+	let view_rotation = mat3x3(camera.view[0].xyz, camera.view[1].xyz, camera.view[2].xyz);
+
+	let clip = camera.proj * vec4(hit, 1.0);
+	var out: ImpostorFragment;
+	out.color = shade(transpose(view_rotation) * normal, in.color);
+	out.depth = clip.z / clip.w;
+	return out;
+}
+
+// Lines
+
+struct LineInput {
+	@location(0) position: vec3<f32>,
+	@location(1) color: vec3<f32>,
+}
+
+struct LineOutput {
+	@builtin(position) clip_position: vec4<f32>,
+	@location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs_line(in: LineInput) -> LineOutput {
+	var out: LineOutput;
+	out.clip_position = camera.view_proj * vec4<f32>(in.position, 1.0);
+	out.color = in.color;
+	return out;
+}
+
+@fragment
+fn fs_line(in: LineOutput) -> @location(0) vec4<f32> {
+	return vec4(in.color, 1.0);
+}
@@ -0,0 +1,763 @@
+use crate::gpu::GpuSolver;
+use crate::render::camera::Camera;
+use crate::render::gui::{self, Controls, Gui, Stats, View};
+use crate::render::renderer::Renderer;
+use crate::sim::clock::Clock;
+use crate::sim::export::Exporter;
+use crate::sim::force::Field;
+use crate::sim::scene::Colormap;
+use crate::sim::surface::Surface;
+use crate::sim::{Attribute, Grab, Particles, Ray, Scene};
+use cgmath::prelude::*;
+use cgmath::{Vector2, Vector3};
+use std::iter;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use winit::event::WindowEvent;
+use winit::window::Window;
+
+// radii of the mouse tools in smoothing lengths
+const BRUSH_RADIUS: f32 = 3.0;
+const GRAB_RADIUS: f32 = 2.0;
+const SPAWN_RADIUS: f32 = 1.0;
+const ERASE_RADIUS: f32 = 1.5;
+// acceleration at the center of the brush
+const BRUSH_STRENGTH: f32 = 0.5;
+
+// What a modified click does to the fluid at the cursor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tool {
+	Push,
+	Pull,
+	Grab,
+	Spawn,
+	Erase,
+}
+
+pub struct State {
+	window: Window,
+	surface: wgpu::Surface,
+	smaa_target: smaa::SmaaTarget,
+	device: wgpu::Device,
+	queue: wgpu::Queue,
+	config: wgpu::SurfaceConfiguration,
+	size: winit::dpi::PhysicalSize<u32>,
+	pub camera: Camera,
+	// kept for resetting
+	scene: Scene,
+	particles: Particles,
+	renderer: Renderer,
+	gui: Gui,
+	// created when switching to the GPU
+	gpu_solver: Option<GpuSolver>,
+	exporter: Option<Exporter>,
+	surface_settings: Surface,
+	// in normalized device coordinates
+	cursor: Vector2<f32>,
+	tool: Option<Tool>,
+	grab: Option<Grab>,
+	clock: Clock,
+	last_update: Instant,
+	// wall clock time of the last simulation step
+	step_time: Duration,
+	fps: f32,
+	timer: Instant,
+}
+
+impl State {
+	pub async fn new(window: Window, scene: &Scene) -> Self {
+		let size = window.inner_size();
+		// resets start over from the same random cloud and colors
+		let mut scene = scene.clone();
+		let seed = scene.resolve_seed();
+		log::info!("seed: {seed}");
+
+		let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+			// backends: wgpu::Backends::all(),
+			// backends: wgpu::Backends::DX12,
+			backends: wgpu::Backends::VULKAN,
+			dx12_shader_compiler: wgpu::Dx12Compiler::default(),
+		});
+
+		let surface = unsafe { instance.create_surface(&window) }.unwrap();
+
+		let adapter = instance
+			.request_adapter(&wgpu::RequestAdapterOptions {
+				// power_preference: wgpu::PowerPreference::default(),
+				power_preference: wgpu::PowerPreference::HighPerformance,
+				compatible_surface: Some(&surface),
+				force_fallback_adapter: false,
+			})
+			.await
+			.unwrap();
+
+		// println!("Selected adapter: {:?}", adapter.get_info());
+
+		let (device, queue) = adapter
+			.request_device(
+				&wgpu::DeviceDescriptor {
+					label: None,
+					features: wgpu::Features::empty(),
+					limits: wgpu::Limits::default(),
+				},
+				None,
+			)
+			.await
+			.unwrap();
+
+		// let surface_caps = surface.get_capabilities(&adapter);
+		// dbg!(surface.get_capabilities(&adapter));
+
+		// for f in surface.get_capabilities(&adapter).formats.iter() {
+		// 	dbg!(f);
+		// 	let srgb = f.is_srgb();
+		// 	dbg!(srgb);
+		// }
+
+		// let surface_format = wgpu::TextureFormat::Rgba8UnormSrgb;
+		// let surface_format = wgpu::TextureFormat::Rgba8Unorm;
+		let surface_format = wgpu::TextureFormat::Bgra8UnormSrgb;
+		// let surface_format = surface.get_capabilities(&adapter).formats[0];
+
+		let config = wgpu::SurfaceConfiguration {
+			usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+			format: surface_format,
+			width: size.width,
+			height: size.height,
+			// Fifo is a strange way to spell vsync
+			// present_mode: wgpu::PresentMode::Fifo,
+			present_mode: wgpu::PresentMode::Mailbox,
+			// present_mode: wgpu::PresentMode::Immediate,
+			alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+			view_formats: vec![],
+		};
+
+		surface.configure(&device, &config);
+
+		let smaa_target = smaa::SmaaTarget::new(
+			&device,
+			&queue,
+			window.inner_size().width,
+			window.inner_size().height,
+			surface_format,
+			smaa::SmaaMode::Smaa1X,
+		);
+
+		// setup
+		let camera = Camera::new(
+			&device,
+			config.width as f32 / config.height as f32,
+			&scene.camera,
+		);
+		let particles = Particles::new(&scene);
+		let mut renderer = Renderer::new(
+			&device,
+			&queue,
+			config.format,
+			config.width,
+			config.height,
+			&camera,
+			&particles,
+		);
+		renderer.set_visualization(&queue, scene.visualization);
+		let gui = Gui::new(&device, surface_format, window.scale_factor() as f32);
+
+		let exporter = scene.export.clone().and_then(|settings| {
+			Exporter::new(settings)
+				.map_err(|e| log::error!("{e:#}"))
+				.ok()
+		});
+
+		Self {
+			window,
+			surface,
+			smaa_target,
+			device,
+			queue,
+			config,
+			size,
+			camera,
+			surface_settings: scene.surface,
+			scene,
+			particles,
+			renderer,
+			gui,
+			gpu_solver: None,
+			exporter,
+			cursor: Vector2::zero(),
+			tool: None,
+			grab: None,
+			clock: Clock::default(),
+			last_update: Instant::now(),
+			step_time: Duration::ZERO,
+			fps: 0.0,
+			timer: Instant::now(),
+		}
+	}
+
+	pub fn window(&self) -> &Window {
+		&self.window
+	}
+
+	pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+		if new_size.width > 0 && new_size.height > 0 {
+			self.camera
+				.set_aspect(new_size.width as f32 / new_size.height as f32);
+			self.size = new_size;
+			self.config.width = new_size.width;
+			self.config.height = new_size.height;
+			self.surface.configure(&self.device, &self.config);
+			self.renderer
+				.resize(&self.device, new_size.width, new_size.height);
+			self.smaa_target
+				.resize(&self.device, new_size.width, new_size.height);
+			self.gui
+				.set_pixels_per_point(self.window.scale_factor() as f32);
+		}
+	}
+
+	// true when the panel takes the event
+	pub fn gui_event(&mut self, event: &WindowEvent) -> bool {
+		self.gui.handle_event(event)
+	}
+
+	// in pixels
+	pub fn move_cursor(&mut self, x: f32, y: f32) {
+		self.cursor = Vector2::new(
+			2.0 * x / self.config.width as f32 - 1.0,
+			1.0 - 2.0 * y / self.config.height as f32,
+		);
+	}
+
+	pub fn begin_interaction(&mut self, tool: Tool) {
+		self.tool = Some(tool);
+		let h = self.particles.h();
+		match tool {
+			Tool::Grab => {
+				self.download();
+				self.grab = self.particles.grab(&self.ray(), GRAB_RADIUS * h);
+				if let Some(grab) = &self.grab {
+					log::info!("grabbed particles at distance {:.0}", grab.distance);
+				}
+			}
+			Tool::Spawn => {
+				let ray = self.ray();
+				let radius = SPAWN_RADIUS * h;
+				// in front of the fluid, not inside it
+				let t = self.pick(&ray) - radius;
+				let spawned = self.edit_particles(|particles| {
+					particles.spawn_ball(ray.at(t), radius);
+				});
+				log::info!("spawned {spawned} particles");
+			}
+			// applied every frame while held
+			Tool::Push | Tool::Pull | Tool::Erase => (),
+		}
+	}
+
+	pub fn end_interaction(&mut self) {
+		self.tool = None;
+		self.grab = None;
+		self.particles.set_brush(None);
+	}
+
+	fn ray(&self) -> Ray {
+		self.camera.ray(self.cursor)
+	}
+
+	// distance to the first particle on the ray, or to the point the camera orbits
+	fn pick(&self, ray: &Ray) -> f32 {
+		self.particles
+			.pick(ray)
+			.unwrap_or_else(|| self.camera.distance())
+	}
+
+	// per frame part of the tools
+	fn interact(&mut self) {
+		let Some(tool) = self.tool else {
+			return;
+		};
+		let h = self.particles.h();
+		match tool {
+			Tool::Push | Tool::Pull => {
+				self.download();
+				let ray = self.ray();
+				let sign = if tool == Tool::Push { 1.0 } else { -1.0 };
+				self.particles.set_brush(Some(Field::Brush {
+					center: ray.at(self.pick(&ray)),
+					radius: BRUSH_RADIUS * h,
+					strength: sign * BRUSH_STRENGTH,
+				}));
+			}
+			Tool::Erase => {
+				self.download();
+				let ray = self.ray();
+				let center = ray.at(self.pick(&ray));
+				self.edit_particles(|particles| {
+					particles.erase(center, ERASE_RADIUS * h);
+				});
+			}
+			// per step, see `step_simulation`
+			Tool::Grab | Tool::Spawn => (),
+		}
+	}
+
+	// runs `edit` on the newest particle state and hands the result back to the GPU solver,
+	// returns the change in particle count
+	fn edit_particles(&mut self, edit: impl FnOnce(&mut Particles)) -> isize {
+		self.download();
+		let before = self.particles.particles().len();
+		edit(&mut self.particles);
+		self.upload();
+		self.particles.particles().len() as isize - before as isize
+	}
+
+	// the renderer makes room for the CPU particles first, the GPU solver writes into its buffer
+	fn upload(&mut self) {
+		self.renderer
+			.update(&self.device, &self.queue, &mut self.particles);
+		if let Some(gpu_solver) = &mut self.gpu_solver {
+			gpu_solver.upload(
+				&self.device,
+				&self.queue,
+				&self.particles,
+				self.renderer.instances(),
+			);
+		}
+	}
+
+	pub fn toggle_gui(&mut self) {
+		self.gui.visible = !self.gui.visible;
+		log::info!("gui: {}", self.gui.visible);
+	}
+
+	// switch between the CPU and the compute shader solver, carrying over the particle state
+	pub fn toggle_gpu(&mut self) {
+		match self.gpu_solver.take() {
+			Some(gpu_solver) => {
+				gpu_solver.download(&self.device, &self.queue, &mut self.particles);
+			}
+			None if self.particles.particles().is_empty() => {
+				log::warn!("no particles to hand to the gpu solver");
+				return;
+			}
+			// rebuilt every time, the particle count may have changed since the last switch
+			None => {
+				self.gpu_solver = Some(GpuSolver::new(
+					&self.device,
+					&self.queue,
+					&self.particles,
+					self.renderer.instances(),
+				));
+			}
+		}
+		log::info!("gpu solver: {}", self.gpu_solver.is_some());
+	}
+
+	pub fn save_checkpoint(&mut self, path: &Path) {
+		// the GPU holds the newest state
+		if let Some(gpu_solver) = &self.gpu_solver {
+			gpu_solver.download(&self.device, &self.queue, &mut self.particles);
+		}
+		match self.particles.save_file(path) {
+			Ok(()) => log::info!("saved checkpoint to {}", path.display()),
+			Err(e) => log::error!("{e:#}"),
+		}
+	}
+
+	// continues on the CPU solver, colliders and capacity can differ from the current scene
+	pub fn load_checkpoint(&mut self, path: &Path) {
+		match Particles::load_file(path) {
+			Ok(particles) => {
+				self.set_particles(particles);
+				log::info!(
+					"loaded checkpoint {} at time {:.1}",
+					path.display(),
+					self.particles.time()
+				);
+			}
+			Err(e) => log::error!("{e:#}"),
+		}
+	}
+
+	// back to the initial conditions of the scene, on the CPU solver
+	pub fn reset(&mut self) {
+		self.set_particles(Particles::new(&self.scene));
+		self.clock.reset();
+		log::info!("reset");
+	}
+
+	pub fn toggle_pause(&mut self) {
+		self.clock.toggle_pause();
+	}
+
+	pub fn step(&mut self) {
+		self.clock.step();
+	}
+
+	pub fn faster(&mut self) {
+		self.clock.faster();
+	}
+
+	pub fn slower(&mut self) {
+		self.clock.slower();
+	}
+
+	// the renderer is rebuilt since the capacity can change, the view is kept
+	fn set_particles(&mut self, particles: Particles) {
+		// grabbed indices mean nothing for the new particles
+		self.end_interaction();
+		self.particles = particles;
+		self.gpu_solver = None;
+		let view = self.view();
+		let visualization = self.renderer.visualization();
+		self.renderer = Renderer::new(
+			&self.device,
+			&self.queue,
+			self.config.format,
+			self.config.width,
+			self.config.height,
+			&self.camera,
+			&self.particles,
+		);
+		self.renderer.set_visualization(&self.queue, visualization);
+		self.set_view(view);
+	}
+
+	fn view(&self) -> View {
+		if self.renderer.surface().is_some() {
+			View::Surface
+		} else if self.renderer.screen_space() {
+			View::ScreenSpace
+		} else {
+			View::Particles
+		}
+	}
+
+	fn set_view(&mut self, view: View) {
+		self.renderer.set_surface(None);
+		self.renderer
+			.set_screen_space(&self.device, &self.particles, false);
+		match view {
+			View::Particles => (),
+			View::Surface => {
+				// the surface is built on the CPU
+				self.download();
+				self.renderer.set_surface(Some(self.surface_settings));
+				self.renderer
+					.update_surface(&self.device, &self.queue, &self.particles);
+			}
+			View::ScreenSpace => {
+				self.renderer
+					.set_screen_space(&self.device, &self.particles, true);
+			}
+		}
+	}
+
+	// switch between drawing particles and the reconstructed surface
+	pub fn toggle_surface(&mut self) {
+		let on = self.view() != View::Surface;
+		self.set_view(if on { View::Surface } else { View::Particles });
+		log::info!("surface: {on}");
+	}
+
+	// switch between drawing particles and the screen space fluid
+	pub fn toggle_screen_space(&mut self) {
+		let on = self.view() != View::ScreenSpace;
+		self.set_view(if on {
+			View::ScreenSpace
+		} else {
+			View::Particles
+		});
+		log::info!("screen space fluid: {on}");
+	}
+
+	// the GPU holds the newest state while its solver runs
+	fn download(&mut self) {
+		if let Some(gpu_solver) = &self.gpu_solver {
+			gpu_solver.download(&self.device, &self.queue, &mut self.particles);
+		}
+	}
+
+	// cycles through coloring by each attribute and the particle colors
+	pub fn cycle_attribute(&mut self) {
+		let mut visualization = self.renderer.visualization();
+		visualization.attribute = match visualization.attribute {
+			None => Some(Attribute::ALL[0]),
+			Some(attribute) => Attribute::ALL
+				.iter()
+				.position(|&a| a == attribute)
+				.and_then(|i| Attribute::ALL.get(i + 1))
+				.copied(),
+		};
+		// a fixed range of one attribute means nothing for the next
+		visualization.range = None;
+		self.renderer.set_visualization(&self.queue, visualization);
+		log::info!(
+			"color by: {}",
+			visualization
+				.attribute
+				.map_or("particle color", Attribute::name)
+		);
+	}
+
+	pub fn cycle_colormap(&mut self) {
+		let mut visualization = self.renderer.visualization();
+		visualization.colormap = match visualization.colormap {
+			Colormap::Viridis => Colormap::Coolwarm,
+			Colormap::Coolwarm => Colormap::Turbo,
+			Colormap::Turbo => Colormap::Viridis,
+		};
+		self.renderer.set_visualization(&self.queue, visualization);
+		log::info!("colormap: {}", visualization.colormap.name());
+	}
+
+	// fixes the colormap range at the current values, or follows the values again
+	pub fn toggle_range(&mut self) {
+		let mut visualization = self.renderer.visualization();
+		visualization.range = match visualization.range {
+			Some(_) => None,
+			None => Some(self.renderer.range()),
+		};
+		self.renderer.set_visualization(&self.queue, visualization);
+		match visualization.range {
+			Some((min, max)) => log::info!("colormap range: fixed at {min} to {max}"),
+			None => log::info!("colormap range: auto"),
+		}
+	}
+
+	pub fn toggle_solver(&mut self) {
+		self.particles.toggle_solver();
+	}
+
+	pub fn toggle_integrator(&mut self) {
+		self.particles.toggle_integrator();
+	}
+
+	pub fn toggle_eos(&mut self) {
+		self.particles.toggle_eos();
+	}
+
+	pub fn toggle_adaptive(&mut self) {
+		self.particles.toggle_adaptive();
+	}
+
+	pub fn toggle_field(&mut self, index: usize) {
+		self.particles.toggle_field(index);
+	}
+
+	pub fn update(&mut self) {
+		let now = Instant::now();
+		let steps = self.clock.advance(now - self.last_update);
+		self.last_update = now;
+
+		self.interact();
+		let start = Instant::now();
+		for _ in 0..steps {
+			self.step_simulation();
+		}
+		if steps > 0 {
+			self.step_time = start.elapsed() / steps as u32;
+		}
+
+		// also while paused, the coloring can change
+		if let Some(gpu_solver) = &self.gpu_solver {
+			if self.renderer.follows_range() {
+				gpu_solver.download(&self.device, &self.queue, &mut self.particles);
+			}
+			self.renderer.update_range(&self.queue, &mut self.particles);
+		} else {
+			self.renderer
+				.update(&self.device, &self.queue, &mut self.particles);
+		}
+		if steps > 0 && self.renderer.surface().is_some() {
+			// the surface is built on the CPU
+			self.download();
+			self.renderer
+				.update_surface(&self.device, &self.queue, &self.particles);
+		}
+		self.camera.update(&self.queue);
+	}
+
+	// one tick of the clock
+	fn step_simulation(&mut self) {
+		if let Some(mut grab) = self.grab.take() {
+			let ray = self.ray();
+			self.edit_particles(|particles| particles.drag(&mut grab, &ray));
+			self.grab = Some(grab);
+		}
+		if self.gpu_solver.is_some() && self.particles.is_open() {
+			// emitters and sinks run on the CPU
+			self.download();
+			let dt = self.particles.timestep().frame_time;
+			if self.particles.update_flow(dt) {
+				self.upload();
+			}
+		}
+		if let Some(gpu_solver) = &mut self.gpu_solver {
+			let attribute = self.renderer.visualization().attribute;
+			gpu_solver.update(&self.device, &self.queue, &self.particles, attribute);
+		} else {
+			self.particles.update();
+		}
+		if let Some(exporter) = &mut self.exporter {
+			if exporter.is_due() {
+				if let Some(gpu_solver) = &self.gpu_solver {
+					gpu_solver.download(&self.device, &self.queue, &mut self.particles);
+				}
+			}
+			if let Err(e) = exporter.frame(&self.particles) {
+				log::error!("{e:#}, export stopped");
+				self.exporter = None;
+			}
+		}
+	}
+
+	// current settings for the panel to edit
+	fn controls(&self) -> Controls {
+		let timestep = self.particles.timestep();
+		let light = self.camera.light;
+		Controls {
+			paused: self.clock.paused,
+			time_scale: self.clock.time_scale,
+			step: false,
+			reset: false,
+			swap_eos: false,
+			gpu: self.gpu_solver.is_some(),
+			solver: self.particles.solver(),
+			integrator: self.particles.integrator(),
+			eos: self.particles.eos(),
+			rest_density: self.particles.rest_density(),
+			viscosity: self.particles.viscosity(),
+			velocity: self.particles.velocity_params(),
+			h: self.particles.h(),
+			frame_time: timestep.frame_time,
+			adaptive: timestep.adaptive,
+			view: self.view(),
+			visualization: self.renderer.visualization(),
+			light_azimuth: light.x.atan2(light.z).to_degrees(),
+			light_elevation: light.y.clamp(-1.0, 1.0).asin().to_degrees(),
+			light_intensity: self.camera.light_intensity,
+		}
+	}
+
+	fn apply(&mut self, mut controls: Controls) {
+		if controls.paused != self.clock.paused {
+			self.clock.toggle_pause();
+		}
+		if controls.step {
+			self.clock.step();
+		}
+		self.clock.set_time_scale(controls.time_scale);
+		if controls.reset {
+			self.reset();
+			return;
+		}
+		if controls.gpu != self.gpu_solver.is_some() {
+			self.toggle_gpu();
+			// refused without particles, the checkbox goes back
+			controls.gpu = self.gpu_solver.is_some();
+		}
+
+		if controls.swap_eos {
+			self.particles.toggle_eos();
+		} else {
+			self.particles.set_eos(controls.eos);
+		}
+		self.particles.set_solver(controls.solver);
+		self.particles.set_integrator(controls.integrator);
+		self.particles.set_rest_density(controls.rest_density);
+		self.particles.set_viscosity(controls.viscosity);
+		self.particles.set_velocity_params(controls.velocity);
+		if controls.h != self.particles.h() {
+			self.particles.set_h(controls.h);
+		}
+		let timestep = self.particles.timestep_mut();
+		timestep.frame_time = controls.frame_time;
+		timestep.adaptive = controls.adaptive;
+
+		if controls.view != self.view() {
+			self.set_view(controls.view);
+		}
+		self.renderer
+			.set_visualization(&self.queue, controls.visualization);
+
+		let (azimuth, elevation) = (
+			controls.light_azimuth.to_radians(),
+			controls.light_elevation.to_radians(),
+		);
+		self.camera.light = Vector3::new(
+			azimuth.sin() * elevation.cos(),
+			elevation.sin(),
+			azimuth.cos() * elevation.cos(),
+		)
+		.normalize();
+		self.camera.light_intensity = controls.light_intensity;
+	}
+
+	fn stats(&self) -> Stats {
+		let stats = self.particles.stats();
+		Stats {
+			fps: self.fps,
+			particles: self.particles.particles().len(),
+			step_time: self.step_time,
+			max_density_error: self.particles.max_density_error(),
+			time: self.particles.time(),
+			substeps: stats.substeps,
+			range: self.renderer.range(),
+		}
+	}
+
+	pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+		// smoothed over a few frames to stay readable
+		let fps = 1.0 / self.timer.elapsed().as_secs_f32().max(1e-6);
+		self.fps += 0.1 * (fps - self.fps);
+		self.timer = Instant::now();
+
+		let size = [self.config.width, self.config.height];
+		if self.gui.visible {
+			let mut controls = self.controls();
+			let stats = self.stats();
+			self.gui.run(size, |context| {
+				gui::panel::show(context, &mut controls, &stats)
+			});
+			self.apply(controls);
+			self.camera.update(&self.queue);
+		}
+
+		let output = self.surface.get_current_texture()?;
+		let view = output
+			.texture
+			.create_view(&wgpu::TextureViewDescriptor::default());
+
+		let smaa_frame = self
+			.smaa_target
+			.start_frame(&self.device, &self.queue, &view);
+
+		let mut encoder = self
+			.device
+			.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+				label: Some("Render Encoder"),
+			});
+
+		self.renderer.render(&mut encoder, &smaa_frame);
+
+		self.queue.submit(iter::once(encoder.finish()));
+		smaa_frame.resolve();
+
+		// drawn after antialiasing, text is sharp already
+		if self.gui.visible {
+			let mut encoder = self
+				.device
+				.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+					label: Some("Gui Encoder"),
+				});
+			self.gui
+				.render(&self.device, &self.queue, &mut encoder, &view, size);
+			self.queue.submit(iter::once(encoder.finish()));
+		}
+		output.present();
+
+		Ok(())
+	}
+}
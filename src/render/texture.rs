@@ -9,12 +9,13 @@ impl Texture {
 
 	pub fn create_depth_texture(
 		device: &wgpu::Device,
-		config: &wgpu::SurfaceConfiguration,
+		width: u32,
+		height: u32,
 		label: &str,
 	) -> Self {
 		let size = wgpu::Extent3d {
-			width: config.width,
-			height: config.height,
+			width,
+			height,
 			depth_or_array_layers: 1,
 		};
 		let desc = wgpu::TextureDescriptor {
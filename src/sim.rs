@@ -0,0 +1,16 @@
+// The simulation itself, free of any GPU or windowing code.
+pub mod clock;
+pub mod collider;
+pub mod emitter;
+pub mod eos;
+pub mod export;
+pub mod force;
+pub mod grid;
+pub mod particle;
+pub mod scene;
+pub mod sink;
+pub mod surface;
+pub mod timestep;
+
+pub use particle::{Attribute, Grab, Integrator, Particle, Particles, Ray, Solver, StepStats};
+pub use scene::Scene;
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+// slow motion to fast forward, `Clock::faster` and `slower` move along these
+pub const TIME_SCALES: [f32; 7] = [0.125, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+// Turns wall clock time into a whole number of simulation steps, so the simulation runs
+// at the same speed whatever the frame rate is. Each step advances `TimeStep::frame_time`.
+#[derive(Debug, Copy, Clone)]
+pub struct Clock {
+	// wall clock time of one step at a time scale of 1
+	pub tick: Duration,
+	pub time_scale: f32,
+	pub paused: bool,
+	// steps per call to `advance`, time beyond that is dropped so a slow step can't snowball
+	pub max_steps: usize,
+	// scaled wall clock time not yet simulated
+	accumulator: Duration,
+	single_step: bool,
+}
+
+impl Default for Clock {
+	fn default() -> Self {
+		Self::new(Duration::from_secs_f64(1.0 / 60.0))
+	}
+}
+
+impl Clock {
+	pub fn new(tick: Duration) -> Self {
+		Self {
+			tick,
+			time_scale: 1.0,
+			paused: false,
+			max_steps: 8,
+			accumulator: Duration::ZERO,
+			single_step: false,
+		}
+	}
+
+	// number of steps to take for `elapsed` wall clock time since the last call
+	pub fn advance(&mut self, elapsed: Duration) -> usize {
+		if self.paused {
+			return usize::from(std::mem::take(&mut self.single_step));
+		}
+		self.accumulator += elapsed.mul_f64(self.time_scale as f64);
+		let mut steps = 0;
+		while self.accumulator >= self.tick {
+			self.accumulator -= self.tick;
+			steps += 1;
+		}
+		if steps > self.max_steps {
+			log::debug!("clock dropped {} steps", steps - self.max_steps);
+			steps = self.max_steps;
+		}
+		steps
+	}
+
+	pub fn toggle_pause(&mut self) {
+		self.paused = !self.paused;
+		// don't catch up on the time spent paused
+		self.accumulator = Duration::ZERO;
+		log::info!("paused: {}", self.paused);
+	}
+
+	// one step on the next call to `advance`, only while paused
+	pub fn step(&mut self) {
+		if self.paused {
+			self.single_step = true;
+		}
+	}
+
+	pub fn reset(&mut self) {
+		self.accumulator = Duration::ZERO;
+		self.single_step = false;
+	}
+
+	pub fn faster(&mut self) {
+		self.set_time_scale(
+			TIME_SCALES
+				.iter()
+				.copied()
+				.find(|&s| s > self.time_scale)
+				.unwrap_or(self.time_scale),
+		);
+	}
+
+	pub fn slower(&mut self) {
+		self.set_time_scale(
+			TIME_SCALES
+				.iter()
+				.rev()
+				.copied()
+				.find(|&s| s < self.time_scale)
+				.unwrap_or(self.time_scale),
+		);
+	}
+
+	pub fn set_time_scale(&mut self, time_scale: f32) {
+		if time_scale != self.time_scale {
+			self.time_scale = time_scale;
+			log::info!("time scale: {time_scale}x");
+		}
+	}
+}
@@ -0,0 +1,231 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+
+use std::f32::consts::PI;
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum Shape {
+	// container, particles are kept inside
+	Box {
+		min: Vector3<f32>,
+		max: Vector3<f32>,
+	},
+	// infinite plane, particles are kept on the side the normal points to
+	Plane {
+		normal: Vector3<f32>,
+		offset: f32,
+	},
+	// solid obstacle, particles are kept outside
+	Sphere {
+		center: Vector3<f32>,
+		radius: f32,
+	},
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Collider {
+	pub shape: Shape,
+	// fraction of the normal velocity that is reflected
+	#[serde(default = "restitution")]
+	pub restitution: f32,
+	// coulomb friction coefficient, scales the tangential velocity loss by the normal impulse
+	#[serde(default = "friction")]
+	pub friction: f32,
+}
+
+fn restitution() -> f32 {
+	0.3
+}
+
+fn friction() -> f32 {
+	0.1
+}
+
+impl Collider {
+	pub fn new(shape: Shape) -> Self {
+		Self {
+			shape,
+			restitution: restitution(),
+			friction: friction(),
+		}
+	}
+
+	// push a particle with the given radius out of the collider and reflect its velocity
+	pub fn resolve(&self, position: &mut Vector3<f32>, velocity: &mut Vector3<f32>, radius: f32) {
+		match self.shape {
+			Shape::Box { min, max } => {
+				// every axis separately, so corners are handled in one go
+				for axis in 0..3 {
+					let mut normal = Vector3::zero();
+					if position[axis] - min[axis] < radius {
+						normal[axis] = 1.0;
+						let depth = min[axis] + radius - position[axis];
+						self.respond(position, velocity, normal, depth);
+					} else if max[axis] - position[axis] < radius {
+						normal[axis] = -1.0;
+						let depth = position[axis] + radius - max[axis];
+						self.respond(position, velocity, normal, depth);
+					}
+				}
+			}
+			Shape::Plane { normal, offset } => {
+				let normal = normal.normalize();
+				let dist = normal.dot(*position) - offset;
+				if dist < radius {
+					self.respond(position, velocity, normal, radius - dist);
+				}
+			}
+			Shape::Sphere {
+				center,
+				radius: sphere_radius,
+			} => {
+				let r = *position - center;
+				let dist = r.magnitude();
+				if dist < sphere_radius + radius && dist > 0.0 {
+					let normal = r / dist;
+					self.respond(position, velocity, normal, sphere_radius + radius - dist);
+				}
+			}
+		}
+	}
+
+	// distance from the surface on the side particles are kept, and the normal pointing to that side
+	pub fn distance(&self, position: Vector3<f32>) -> (f32, Vector3<f32>) {
+		match self.shape {
+			Shape::Box { min, max } => {
+				// the nearest wall only
+				let mut nearest = (f32::INFINITY, Vector3::zero());
+				for axis in 0..3 {
+					let mut normal = Vector3::zero();
+					normal[axis] = 1.0;
+					for (distance, normal) in [
+						(position[axis] - min[axis], normal),
+						(max[axis] - position[axis], -normal),
+					] {
+						if distance < nearest.0 {
+							nearest = (distance, normal);
+						}
+					}
+				}
+				nearest
+			}
+			Shape::Plane { normal, offset } => {
+				let normal = normal.normalize();
+				(normal.dot(position) - offset, normal)
+			}
+			Shape::Sphere { center, radius } => {
+				let r = position - center;
+				let dist = r.magnitude();
+				let normal = if dist > 0.0 {
+					r / dist
+				} else {
+					Vector3::unit_y()
+				};
+				(dist - radius, normal)
+			}
+		}
+	}
+
+	fn respond(
+		&self,
+		position: &mut Vector3<f32>,
+		velocity: &mut Vector3<f32>,
+		normal: Vector3<f32>,
+		depth: f32,
+	) {
+		*position += normal * depth;
+
+		let v_n = velocity.dot(normal);
+		if v_n < 0.0 {
+			let v_t = *velocity - normal * v_n;
+			let v_t_len = v_t.magnitude();
+
+			// normal impulse is (1 + e) * |v_n|, friction removes at most all tangential motion
+			let impulse = (1.0 + self.restitution) * -v_n;
+			let v_t = if v_t_len > 0.0 {
+				v_t * (1.0 - self.friction * impulse / v_t_len).max(0.0)
+			} else {
+				v_t
+			};
+
+			*velocity = v_t - normal * (v_n * self.restitution);
+		}
+	}
+
+	// wireframe line segments for drawing
+	pub fn lines(&self) -> Vec<[Vector3<f32>; 2]> {
+		match self.shape {
+			Shape::Box { min, max } => {
+				let corner = |i: usize| {
+					Vector3::new(
+						if i & 1 == 0 { min.x } else { max.x },
+						if i & 2 == 0 { min.y } else { max.y },
+						if i & 4 == 0 { min.z } else { max.z },
+					)
+				};
+				// corners that differ in exactly one bit share an edge
+				let mut lines = Vec::new();
+				for i in 0..8 {
+					for bit in [1, 2, 4] {
+						if i & bit == 0 {
+							lines.push([corner(i), corner(i | bit)]);
+						}
+					}
+				}
+				lines
+			}
+			Shape::Plane { normal, offset } => {
+				const SIZE: f32 = 200.0;
+				const DIVISIONS: i32 = 10;
+
+				let normal = normal.normalize();
+				let center = normal * offset;
+				let helper = if normal.y.abs() < 0.9 {
+					Vector3::unit_y()
+				} else {
+					Vector3::unit_x()
+				};
+				let tangent = normal.cross(helper).normalize();
+				let bitangent = normal.cross(tangent);
+
+				let mut lines = Vec::new();
+				for i in -DIVISIONS..=DIVISIONS {
+					let t = SIZE * i as f32 / DIVISIONS as f32;
+					lines.push([
+						center + tangent * t - bitangent * SIZE,
+						center + tangent * t + bitangent * SIZE,
+					]);
+					lines.push([
+						center + bitangent * t - tangent * SIZE,
+						center + bitangent * t + tangent * SIZE,
+					]);
+				}
+				lines
+			}
+			Shape::Sphere { center, radius } => {
+				const SEGMENTS: usize = 48;
+
+				let point = |axis: usize, k: usize| {
+					let a = 2.0 * PI * k as f32 / SEGMENTS as f32;
+					let (s, c) = a.sin_cos();
+					let v = match axis {
+						0 => Vector3::new(0.0, c, s),
+						1 => Vector3::new(c, 0.0, s),
+						_ => Vector3::new(c, s, 0.0),
+					};
+					center + v * radius
+				};
+
+				// three great circles
+				let mut lines = Vec::new();
+				for axis in 0..3 {
+					for k in 0..SEGMENTS {
+						lines.push([point(axis, k), point(axis, k + 1)]);
+					}
+				}
+				lines
+			}
+		}
+	}
+}
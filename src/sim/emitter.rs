@@ -0,0 +1,110 @@
+use super::scene::{lattice, zero};
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+
+// Sources that add particles while the simulation runs.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum Source {
+	// disc of lattice points shooting fluid along `direction` at `speed`, `rate` particles per
+	// unit of time in whole layers of the disc, so the stream is packed at the rest spacing for
+	// rate = speed * layer size / spacing, sparser below and overlapping above
+	Nozzle {
+		center: Vector3<f32>,
+		direction: Vector3<f32>,
+		radius: f32,
+		speed: f32,
+		rate: f32,
+	},
+	// keeps a box full of fluid moving at `velocity`, lattice points the fluid
+	// flowed away from are refilled every step, like the inlet of a channel
+	Volume {
+		min: Vector3<f32>,
+		max: Vector3<f32>,
+		#[serde(default = "zero")]
+		velocity: Vector3<f32>,
+	},
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Emitter {
+	pub source: Source,
+	#[serde(default = "enabled")]
+	pub enabled: bool,
+	// particles due since the last nozzle layer, less than one layer
+	#[serde(default)]
+	due: f32,
+}
+
+fn enabled() -> bool {
+	true
+}
+
+impl Emitter {
+	pub fn new(source: Source) -> Self {
+		Self {
+			source,
+			enabled: true,
+			due: 0.0,
+		}
+	}
+
+	// whether `emit` looks at where the particles are
+	pub fn fills(&self) -> bool {
+		self.enabled && matches!(self.source, Source::Volume { .. })
+	}
+
+	// pushes the positions and velocities of the particles spawned during `dt`,
+	// `occupied` tells whether a particle is already close to a point
+	pub fn emit(
+		&mut self,
+		dt: f32,
+		spacing: f32,
+		occupied: impl Fn(Vector3<f32>) -> bool,
+		out: &mut Vec<(Vector3<f32>, Vector3<f32>)>,
+	) {
+		if !self.enabled {
+			return;
+		}
+		match self.source {
+			Source::Nozzle {
+				center,
+				direction,
+				radius,
+				speed,
+				rate,
+			} => {
+				let direction = direction.normalize();
+				let velocity = direction * speed;
+				// any two vectors perpendicular to the direction span the disc
+				let helper = if direction.x.abs() < 0.9 {
+					Vector3::unit_x()
+				} else {
+					Vector3::unit_y()
+				};
+				let u = direction.cross(helper).normalize();
+				let v = direction.cross(u);
+				let n = (radius / spacing) as i32;
+				let disc = (-n..=n)
+					.flat_map(|a| (-n..=n).map(move |b| (u * a as f32 + v * b as f32) * spacing))
+					.filter(|offset| offset.magnitude2() <= radius * radius)
+					.collect::<Vec<_>>();
+
+				self.due += rate.max(0.0) * dt;
+				while self.due >= disc.len() as f32 {
+					self.due -= disc.len() as f32;
+					// the layer was due `due / rate` ago and has moved since
+					let layer = center + velocity * (self.due / rate);
+					out.extend(disc.iter().map(|&offset| (layer + offset, velocity)));
+				}
+			}
+			Source::Volume { min, max, velocity } => {
+				out.extend(
+					lattice(min, max, spacing)
+						.filter(|&x| !occupied(x))
+						.map(|x| (x, velocity)),
+				);
+			}
+		}
+	}
+}
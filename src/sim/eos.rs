@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+// Equation of state, relates density to pressure for the state equation solver.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum EquationOfState {
+	// p = k (rho - rho_0)
+	IdealGas { gas_const: f32 },
+	// Tait/Cole: p = B ((rho / rho_0)^gamma - 1),
+	// with B picked so the speed of sound at rest density is `speed_of_sound`
+	Tait { speed_of_sound: f32, gamma: f32 },
+}
+
+impl Default for EquationOfState {
+	fn default() -> Self {
+		Self::IdealGas { gas_const: 50.0 }
+	}
+}
+
+impl EquationOfState {
+	// the other kind with its default parameters
+	pub fn other(&self) -> Self {
+		match self {
+			Self::IdealGas { .. } => Self::Tait {
+				speed_of_sound: 10.0,
+				gamma: 7.0,
+			},
+			Self::Tait { .. } => Self::default(),
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::IdealGas { .. } => "ideal gas",
+			Self::Tait { .. } => "tait",
+		}
+	}
+
+	// at rest density, sqrt(dp/drho)
+	pub fn speed_of_sound(&self) -> f32 {
+		match *self {
+			Self::IdealGas { gas_const } => gas_const.sqrt(),
+			Self::Tait { speed_of_sound, .. } => speed_of_sound,
+		}
+	}
+
+	// k for the ideal gas, B for Tait
+	pub fn stiffness(&self, rest_density: f32) -> f32 {
+		match *self {
+			Self::IdealGas { gas_const } => gas_const,
+			// c^2 = dp/drho = B gamma / rho_0
+			Self::Tait {
+				speed_of_sound,
+				gamma,
+			} => rest_density * speed_of_sound.powi(2) / gamma,
+		}
+	}
+
+	pub fn pressure(&self, density: f32, rest_density: f32) -> f32 {
+		match *self {
+			Self::IdealGas { gas_const } => gas_const * (density - rest_density),
+			// negative pressure at the free surface clumps particles, so it is clamped
+			Self::Tait { gamma, .. } => {
+				let stiffness = self.stiffness(rest_density);
+				(stiffness * ((density / rest_density).powf(gamma) - 1.0)).max(0.0)
+			}
+		}
+	}
+}
@@ -0,0 +1,423 @@
+use super::surface::Surface;
+use super::{Particle, Particles};
+use anyhow::{bail, Context};
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, Read, Write};
+use std::path::PathBuf;
+
+// Particle data files for ParaView, Houdini and friends.
+// Every format holds position, velocity, density, pressure, mass, radius and color.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Format {
+	// legacy ASCII VTK polydata
+	Vtk,
+	// binary little endian PLY, color is stored as bytes
+	Ply,
+	Csv,
+}
+
+impl Format {
+	pub fn extension(&self) -> &'static str {
+		match self {
+			Self::Vtk => "vtk",
+			Self::Ply => "ply",
+			Self::Csv => "csv",
+		}
+	}
+}
+
+pub fn write(format: Format, particles: &[Particle], writer: impl Write) -> std::io::Result<()> {
+	let mut writer = std::io::BufWriter::new(writer);
+	match format {
+		Format::Vtk => write_vtk(particles, &mut writer)?,
+		Format::Ply => write_ply(particles, &mut writer)?,
+		Format::Csv => write_csv(particles, &mut writer)?,
+	}
+	writer.flush()
+}
+
+// reads back what `write` produced, forces are zero
+pub fn read(format: Format, reader: impl Read) -> anyhow::Result<Vec<Particle>> {
+	let mut reader = std::io::BufReader::new(reader);
+	match format {
+		Format::Vtk => read_vtk(&mut reader),
+		Format::Ply => read_ply(&mut reader),
+		Format::Csv => read_csv(&mut reader),
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSettings {
+	pub format: Format,
+	pub dir: PathBuf,
+	// export every n-th frame
+	#[serde(default = "interval")]
+	pub interval: usize,
+	// also write the reconstructed liquid surface as frame_00000.obj, ...
+	#[serde(default)]
+	pub surface: Option<Surface>,
+}
+
+fn interval() -> usize {
+	1
+}
+
+// Writes every `interval`-th frame to dir/frame_00000.ext, dir/frame_00001.ext, ...
+pub struct Exporter {
+	settings: ExportSettings,
+	frame: usize,
+	written: usize,
+}
+
+impl Exporter {
+	pub fn new(settings: ExportSettings) -> anyhow::Result<Self> {
+		std::fs::create_dir_all(&settings.dir)
+			.with_context(|| format!("could not create {}", settings.dir.display()))?;
+		Ok(Self {
+			settings,
+			frame: 0,
+			written: 0,
+		})
+	}
+
+	// whether the next call to `frame` writes a file
+	pub fn is_due(&self) -> bool {
+		self.frame % self.settings.interval.max(1) == 0
+	}
+
+	// call once per simulated frame
+	pub fn frame(&mut self, particles: &Particles) -> anyhow::Result<()> {
+		if self.is_due() {
+			let path = self.path(self.settings.format.extension());
+			let file = std::fs::File::create(&path)
+				.with_context(|| format!("could not create {}", path.display()))?;
+			write(self.settings.format, particles.particles(), file)?;
+			if let Some(surface) = &self.settings.surface {
+				let path = self.path("obj");
+				let file = std::fs::File::create(&path)
+					.with_context(|| format!("could not create {}", path.display()))?;
+				surface.reconstruct(particles.particles()).write_obj(file)?;
+			}
+			self.written += 1;
+		}
+		self.frame += 1;
+		Ok(())
+	}
+
+	fn path(&self, extension: &str) -> PathBuf {
+		self.settings
+			.dir
+			.join(format!("frame_{:05}.{extension}", self.written))
+	}
+}
+
+type Attribute = fn(&Particle) -> f32;
+
+fn write_vtk(particles: &[Particle], w: &mut impl Write) -> std::io::Result<()> {
+	let n = particles.len();
+	writeln!(w, "# vtk DataFile Version 3.0")?;
+	writeln!(w, "wgpu_fluid particles")?;
+	writeln!(w, "ASCII")?;
+	writeln!(w, "DATASET POLYDATA")?;
+	writeln!(w, "POINTS {n} float")?;
+	for p in particles {
+		writeln!(w, "{} {} {}", p.position.x, p.position.y, p.position.z)?;
+	}
+	// one vertex cell per point so the points show up without a glyph filter
+	writeln!(w, "VERTICES {n} {}", 2 * n)?;
+	for i in 0..n {
+		writeln!(w, "1 {i}")?;
+	}
+
+	writeln!(w, "POINT_DATA {n}")?;
+	writeln!(w, "VECTORS velocity float")?;
+	for p in particles {
+		writeln!(w, "{} {} {}", p.velocity.x, p.velocity.y, p.velocity.z)?;
+	}
+	let scalars: [(&str, Attribute); 4] = [
+		("density", |p| p.density),
+		("pressure", |p| p.pressure),
+		("mass", |p| p.mass),
+		("radius", |p| p.radius),
+	];
+	for (name, value) in scalars {
+		writeln!(w, "SCALARS {name} float 1")?;
+		writeln!(w, "LOOKUP_TABLE default")?;
+		for p in particles {
+			writeln!(w, "{}", value(p))?;
+		}
+	}
+	writeln!(w, "COLOR_SCALARS color 3")?;
+	for p in particles {
+		writeln!(w, "{} {} {}", p.color[0], p.color[1], p.color[2])?;
+	}
+	Ok(())
+}
+
+fn read_vtk(r: &mut impl BufRead) -> anyhow::Result<Vec<Particle>> {
+	let mut text = String::new();
+	r.read_to_string(&mut text)?;
+	let mut lines = text.lines();
+	if !lines.next().unwrap_or("").starts_with("# vtk DataFile") {
+		bail!("not a legacy VTK file");
+	}
+	// title line
+	lines.next();
+	if lines.next().map(str::trim) != Some("ASCII") {
+		bail!("only ASCII VTK files are supported");
+	}
+
+	let mut tokens = lines.flat_map(str::split_whitespace);
+	let mut positions = Vec::new();
+	let mut data = HashMap::new();
+	while let Some(keyword) = tokens.next() {
+		match keyword {
+			"DATASET" | "POINT_DATA" => {
+				token(&mut tokens)?;
+			}
+			"POINTS" => {
+				let n = token(&mut tokens)?.parse::<usize>()?;
+				token(&mut tokens)?;
+				positions = floats(&mut tokens, 3 * n)?;
+			}
+			"VERTICES" => {
+				token(&mut tokens)?;
+				let size = token(&mut tokens)?.parse::<usize>()?;
+				for _ in 0..size {
+					token(&mut tokens)?;
+				}
+			}
+			"VECTORS" => {
+				let name = token(&mut tokens)?;
+				token(&mut tokens)?;
+				data.insert(name, floats(&mut tokens, positions.len())?);
+			}
+			"SCALARS" => {
+				// name, type, component count and the LOOKUP_TABLE line
+				let name = token(&mut tokens)?;
+				for _ in 0..4 {
+					token(&mut tokens)?;
+				}
+				data.insert(name, floats(&mut tokens, positions.len() / 3)?);
+			}
+			"COLOR_SCALARS" => {
+				let name = token(&mut tokens)?;
+				token(&mut tokens)?;
+				data.insert(name, floats(&mut tokens, positions.len())?);
+			}
+			other => bail!("unexpected VTK keyword {other}"),
+		}
+	}
+
+	let get = |name: &str| {
+		data.get(name)
+			.with_context(|| format!("VTK file has no {name}"))
+	};
+	let (velocity, density, pressure) = (get("velocity")?, get("density")?, get("pressure")?);
+	let (mass, radius, color) = (get("mass")?, get("radius")?, get("color")?);
+	Ok((0..positions.len() / 3)
+		.map(|i| Particle {
+			position: vector(&positions[3 * i..]),
+			velocity: vector(&velocity[3 * i..]),
+			force: Vector3::zero(),
+			radius: radius[i],
+			mass: mass[i],
+			density: density[i],
+			pressure: pressure[i],
+			color: [color[3 * i], color[3 * i + 1], color[3 * i + 2]],
+		})
+		.collect())
+}
+
+const PLY_FLOATS: [&str; 10] = [
+	"x", "y", "z", "vx", "vy", "vz", "density", "pressure", "mass", "radius",
+];
+const PLY_COLORS: [&str; 3] = ["red", "green", "blue"];
+
+fn write_ply(particles: &[Particle], w: &mut impl Write) -> std::io::Result<()> {
+	writeln!(w, "ply")?;
+	writeln!(w, "format binary_little_endian 1.0")?;
+	writeln!(w, "comment wgpu_fluid particles")?;
+	writeln!(w, "element vertex {}", particles.len())?;
+	for name in PLY_FLOATS {
+		writeln!(w, "property float {name}")?;
+	}
+	for name in PLY_COLORS {
+		writeln!(w, "property uchar {name}")?;
+	}
+	writeln!(w, "end_header")?;
+
+	for p in particles {
+		let floats = [
+			p.position.x,
+			p.position.y,
+			p.position.z,
+			p.velocity.x,
+			p.velocity.y,
+			p.velocity.z,
+			p.density,
+			p.pressure,
+			p.mass,
+			p.radius,
+		];
+		for f in floats {
+			w.write_all(&f.to_le_bytes())?;
+		}
+		for c in p.color {
+			w.write_all(&[(c.clamp(0.0, 1.0) * 255.0).round() as u8])?;
+		}
+	}
+	Ok(())
+}
+
+fn read_ply(r: &mut impl BufRead) -> anyhow::Result<Vec<Particle>> {
+	let mut line = String::new();
+	let mut header_line = |r: &mut dyn BufRead| -> anyhow::Result<String> {
+		line.clear();
+		if r.read_line(&mut line)? == 0 {
+			bail!("unexpected end of PLY header");
+		}
+		Ok(line.trim().to_string())
+	};
+
+	if header_line(r)? != "ply" {
+		bail!("not a PLY file");
+	}
+	let mut count = 0;
+	// name and whether it is a float (otherwise uchar), in file order
+	let mut properties = Vec::new();
+	loop {
+		let line = header_line(r)?;
+		let words = line.split_whitespace().collect::<Vec<_>>();
+		match words.as_slice() {
+			["format", format, _] if *format != "binary_little_endian" => {
+				bail!("only binary little endian PLY files are supported")
+			}
+			["element", "vertex", n] => count = n.parse()?,
+			["property", "float", name] => properties.push((name.to_string(), true)),
+			["property", "uchar", name] => properties.push((name.to_string(), false)),
+			["property", ..] => bail!("unsupported PLY property {line}"),
+			["end_header"] => break,
+			_ => {}
+		}
+	}
+
+	let mut particles = Vec::with_capacity(count);
+	let mut values = HashMap::new();
+	for _ in 0..count {
+		for (name, is_float) in &properties {
+			let value = if *is_float {
+				let mut bytes = [0; 4];
+				r.read_exact(&mut bytes)?;
+				f32::from_le_bytes(bytes)
+			} else {
+				let mut byte = [0; 1];
+				r.read_exact(&mut byte)?;
+				byte[0] as f32 / 255.0
+			};
+			values.insert(name.as_str(), value);
+		}
+		let get = |name: &str| {
+			values
+				.get(name)
+				.copied()
+				.with_context(|| format!("PLY file has no {name}"))
+		};
+		particles.push(Particle {
+			position: Vector3::new(get("x")?, get("y")?, get("z")?),
+			velocity: Vector3::new(get("vx")?, get("vy")?, get("vz")?),
+			force: Vector3::zero(),
+			radius: get("radius")?,
+			mass: get("mass")?,
+			density: get("density")?,
+			pressure: get("pressure")?,
+			color: [get("red")?, get("green")?, get("blue")?],
+		});
+	}
+	Ok(particles)
+}
+
+const CSV_COLUMNS: [&str; 13] = [
+	"x", "y", "z", "vx", "vy", "vz", "density", "pressure", "mass", "radius", "r", "g", "b",
+];
+
+fn write_csv(particles: &[Particle], w: &mut impl Write) -> std::io::Result<()> {
+	writeln!(w, "{}", CSV_COLUMNS.join(","))?;
+	for p in particles {
+		writeln!(
+			w,
+			"{},{},{},{},{},{},{},{},{},{},{},{},{}",
+			p.position.x,
+			p.position.y,
+			p.position.z,
+			p.velocity.x,
+			p.velocity.y,
+			p.velocity.z,
+			p.density,
+			p.pressure,
+			p.mass,
+			p.radius,
+			p.color[0],
+			p.color[1],
+			p.color[2]
+		)?;
+	}
+	Ok(())
+}
+
+fn read_csv(r: &mut impl BufRead) -> anyhow::Result<Vec<Particle>> {
+	let mut lines = r.lines();
+	let header = lines.next().context("empty CSV file")??;
+	let columns = header.split(',').map(str::trim).collect::<Vec<_>>();
+	let index = CSV_COLUMNS
+		.iter()
+		.map(|name| {
+			columns
+				.iter()
+				.position(|c| c == name)
+				.with_context(|| format!("CSV file has no {name} column"))
+		})
+		.collect::<anyhow::Result<Vec<_>>>()?;
+
+	let mut particles = Vec::new();
+	for line in lines {
+		let line = line?;
+		if line.trim().is_empty() {
+			continue;
+		}
+		let fields = line
+			.split(',')
+			.map(|f| f.trim().parse::<f32>())
+			.collect::<Result<Vec<_>, _>>()?;
+		let v = |column: usize| fields.get(index[column]).copied().context("short CSV row");
+		particles.push(Particle {
+			position: Vector3::new(v(0)?, v(1)?, v(2)?),
+			velocity: Vector3::new(v(3)?, v(4)?, v(5)?),
+			force: Vector3::zero(),
+			density: v(6)?,
+			pressure: v(7)?,
+			mass: v(8)?,
+			radius: v(9)?,
+			color: [v(10)?, v(11)?, v(12)?],
+		});
+	}
+	Ok(particles)
+}
+
+fn token<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> anyhow::Result<&'a str> {
+	tokens.next().context("unexpected end of VTK file")
+}
+
+fn floats<'a>(
+	tokens: &mut impl Iterator<Item = &'a str>,
+	count: usize,
+) -> anyhow::Result<Vec<f32>> {
+	(0..count).map(|_| Ok(token(tokens)?.parse()?)).collect()
+}
+
+fn vector(v: &[f32]) -> Vector3<f32> {
+	Vector3::new(v[0], v[1], v[2])
+}
@@ -0,0 +1,108 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// External force fields acting on every particle.
+// Forces are per unit volume like the SPH forces, so they get divided by density on integration.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum Field {
+	// uniform acceleration
+	Gravity { acceleration: Vector3<f32> },
+	// linear spring toward `center`, negative strength repels
+	Point { center: Vector3<f32>, strength: f32 },
+	// swirl around the line through `center` along `axis`
+	Vortex {
+		center: Vector3<f32>,
+		axis: Vector3<f32>,
+		strength: f32,
+	},
+	// linear drag against the velocity
+	Drag { coefficient: f32 },
+	// random kicks, brownian motion
+	Noise { amplitude: f32 },
+	// pushes away from `center` like an acceleration, fading out at `radius`, negative strength pulls
+	Brush {
+		center: Vector3<f32>,
+		radius: f32,
+		strength: f32,
+	},
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct ForceField {
+	pub field: Field,
+	#[serde(default = "enabled")]
+	pub enabled: bool,
+}
+
+fn enabled() -> bool {
+	true
+}
+
+impl ForceField {
+	pub fn new(field: Field) -> Self {
+		Self {
+			field,
+			enabled: true,
+		}
+	}
+
+	pub fn disabled(field: Field) -> Self {
+		Self {
+			field,
+			enabled: false,
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self.field {
+			Field::Gravity { .. } => "gravity",
+			Field::Point { .. } => "point",
+			Field::Vortex { .. } => "vortex",
+			Field::Drag { .. } => "drag",
+			Field::Noise { .. } => "noise",
+			Field::Brush { .. } => "brush",
+		}
+	}
+
+	pub fn force(
+		&self,
+		position: Vector3<f32>,
+		velocity: Vector3<f32>,
+		density: f32,
+		rng: &mut impl Rng,
+	) -> Vector3<f32> {
+		if !self.enabled {
+			return Vector3::zero();
+		}
+		match self.field {
+			Field::Gravity { acceleration } => density * acceleration,
+			Field::Point { center, strength } => strength * (center - position),
+			Field::Vortex {
+				center,
+				axis,
+				strength,
+			} => strength * axis.normalize().cross(position - center),
+			Field::Drag { coefficient } => -coefficient * velocity,
+			Field::Noise { amplitude } => {
+				let x = rng.sample::<f32, _>(rand_distr::StandardNormal);
+				let y = rng.sample::<f32, _>(rand_distr::StandardNormal);
+				let z = rng.sample::<f32, _>(rand_distr::StandardNormal);
+				amplitude * Vector3 { x, y, z }
+			}
+			Field::Brush {
+				center,
+				radius,
+				strength,
+			} => {
+				let d = position - center;
+				let r = d.magnitude();
+				if r >= radius || r < 1e-6 {
+					return Vector3::zero();
+				}
+				density * strength * (1.0 - r / radius) * d / r
+			}
+		}
+	}
+}
@@ -0,0 +1,92 @@
+use cgmath::Vector3;
+
+// Uniform grid spatial hash for fixed radius neighbor queries.
+// Cells are `cell_size` wide, so all neighbors within that radius
+// live in the 3x3x3 block of cells around a query point.
+pub struct Grid {
+	cell_size: f32,
+	// cell coordinates of every particle, used to reject hash collisions
+	cells: Vec<[i32; 3]>,
+	// particles in bucket k are entries[start[k]..start[k + 1]]
+	start: Vec<usize>,
+	entries: Vec<usize>,
+}
+
+impl Grid {
+	pub fn new(cell_size: f32) -> Self {
+		Self {
+			cell_size,
+			cells: Vec::new(),
+			start: vec![0; 2],
+			entries: Vec::new(),
+		}
+	}
+
+	fn cell(&self, position: Vector3<f32>) -> [i32; 3] {
+		cell_of(position, self.cell_size)
+	}
+
+	fn bucket(&self, cell: [i32; 3]) -> usize {
+		let h = (cell[0].wrapping_mul(92_837_111))
+			^ (cell[1].wrapping_mul(689_287_499))
+			^ (cell[2].wrapping_mul(283_923_481));
+		h.unsigned_abs() as usize % (self.start.len() - 1)
+	}
+
+	// counting sort of particle indices into hash buckets
+	pub fn build(&mut self, positions: impl ExactSizeIterator<Item = Vector3<f32>>) {
+		let n = positions.len();
+		let table_size = (2 * n).max(1);
+
+		self.cells.clear();
+		let cell_size = self.cell_size;
+		self.cells.extend(positions.map(|p| cell_of(p, cell_size)));
+
+		self.start.clear();
+		self.start.resize(table_size + 1, 0);
+		for i in 0..n {
+			let k = self.bucket(self.cells[i]);
+			self.start[k] += 1;
+		}
+
+		// prefix sum, each bucket now points one past its end
+		for k in 1..=table_size {
+			self.start[k] += self.start[k - 1];
+		}
+
+		self.entries.clear();
+		self.entries.resize(n, 0);
+		for i in 0..n {
+			let k = self.bucket(self.cells[i]);
+			self.start[k] -= 1;
+			self.entries[self.start[k]] = i;
+		}
+	}
+
+	// calls `f` with every particle in the cells around `position`
+	// caller still has to check the actual distance
+	pub fn for_each_candidate(&self, position: Vector3<f32>, mut f: impl FnMut(usize)) {
+		let [cx, cy, cz] = self.cell(position);
+		for x in cx - 1..=cx + 1 {
+			for y in cy - 1..=cy + 1 {
+				for z in cz - 1..=cz + 1 {
+					let cell = [x, y, z];
+					let k = self.bucket(cell);
+					for &j in &self.entries[self.start[k]..self.start[k + 1]] {
+						if self.cells[j] == cell {
+							f(j);
+						}
+					}
+				}
+			}
+		}
+	}
+}
+
+fn cell_of(position: Vector3<f32>, cell_size: f32) -> [i32; 3] {
+	[
+		(position.x / cell_size).floor() as i32,
+		(position.y / cell_size).floor() as i32,
+		(position.z / cell_size).floor() as i32,
+	]
+}
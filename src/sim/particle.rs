@@ -0,0 +1,627 @@
+use super::collider::Collider;
+use super::emitter::Emitter;
+use super::eos::EquationOfState;
+use super::force::{Field, ForceField};
+use super::grid::Grid;
+use super::scene::{Material, Scene};
+use super::sink::Sink;
+use super::timestep::TimeStep;
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+mod attribute;
+mod checkpoint;
+mod dfsph;
+mod integrator;
+mod interaction;
+mod pbf;
+mod tension;
+mod velocity;
+mod viscosity;
+
+pub use attribute::Attribute;
+pub use dfsph::DfsphParams;
+pub use integrator::Integrator;
+pub use interaction::{Grab, Ray};
+pub use pbf::PbfParams;
+pub use tension::SurfaceTension;
+pub use velocity::VelocityParams;
+pub use viscosity::Viscosity;
+
+use std::f32::consts::PI;
+
+// (h^2 - r^2)^3
+fn w_poly6(r_squared: f32, h: f32) -> f32 {
+	(315.0 / (64.0 * PI * h.powi(9))) * (h.powi(2) - r_squared).powi(3)
+}
+
+// gradient with respect to particle i is r_ij times this, like `spiky_grad`
+fn w_poly6_grad(r_squared: f32, h: f32) -> f32 {
+	(945.0 / (32.0 * PI * h.powi(9))) * (h.powi(2) - r_squared).powi(2)
+}
+
+fn w_poly6_lap(r_squared: f32, h: f32) -> f32 {
+	-(945.0 / (32.0 * PI * h.powi(9)))
+		* (h.powi(2) - r_squared)
+		* (3.0 * h.powi(2) - 7.0 * r_squared)
+}
+
+fn w_spiky_grad(r_squared: f32, h: f32) -> f32 {
+	(45.0 / (PI * h.powi(6))) * (h - r_squared.sqrt()).powi(2)
+}
+
+fn w_visc(r_squared: f32, h: f32) -> f32 {
+	(45.0 / (PI * h.powi(6))) * (h - r_squared.sqrt())
+}
+
+// spiky kernel gradient with respect to particle i, r_ij points from i to j
+fn spiky_grad(r_ij: Vector3<f32>, r_sq: f32, h: f32) -> Vector3<f32> {
+	r_ij * (w_spiky_grad(r_sq, h) / r_sq.sqrt())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Solver {
+	// explicit forces from the equation of state
+	StateEquation,
+	// position based fluids
+	Pbf,
+	// divergence-free SPH
+	Dfsph,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StepStats {
+	pub substeps: usize,
+	pub dt: f32,
+	pub density_iterations: usize,
+	pub divergence_iterations: usize,
+	pub viscosity_iterations: usize,
+	// average relative density error reported by the solver
+	pub density_error: f32,
+}
+
+// TODO: try struct of arrays perf
+pub struct Particles {
+	list: Vec<Particle>,
+	max_particles: usize,
+	grid: Grid,
+	h: f32,
+	material: Material,
+	colliders: Vec<Collider>,
+	fields: Vec<ForceField>,
+	// set by the viewer while the mouse pushes the fluid, not saved in checkpoints
+	brush: Option<ForceField>,
+	emitters: Vec<Emitter>,
+	sinks: Vec<Sink>,
+	eos: EquationOfState,
+	// swapped in by `toggle_eos`
+	other_eos: EquationOfState,
+	rest_density: f32,
+	viscosity: Viscosity,
+	solver: Solver,
+	integrator: Integrator,
+	pbf: PbfParams,
+	dfsph: DfsphParams,
+	velocity: VelocityParams,
+	// DFSPH factors, per particle
+	alpha: Vec<f32>,
+	// vorticity left by vorticity confinement, per particle, empty while it is off
+	omega: Vec<Vector3<f32>>,
+	stats: StepStats,
+	timestep: TimeStep,
+	// largest acceleration of the last step, for the force time step limit
+	max_acceleration: f32,
+	// simulated time
+	time: f64,
+	// counts removals of particles, which shift the indices of the ones after them
+	removals: u64,
+	// every random draw of the simulation comes from here, so runs can be reproduced and resumed
+	rng: Pcg32,
+}
+
+impl Default for Particles {
+	fn default() -> Self {
+		Self::new(&Scene::default())
+	}
+}
+
+impl Particles {
+	pub fn new(scene: &Scene) -> Self {
+		let settings = scene.solver;
+		let material = scene.material;
+		let rest_density = material.rest_density;
+
+		let mut particles = Self {
+			list: Vec::new(),
+			max_particles: scene.max_particles,
+			grid: Grid::new(settings.h),
+			h: settings.h,
+			material,
+			colliders: scene.colliders.clone(),
+			fields: scene.fields.clone(),
+			brush: None,
+			emitters: scene.emitters.clone(),
+			sinks: scene.sinks.clone(),
+			eos: settings.eos,
+			other_eos: settings.other_eos.unwrap_or_else(|| settings.eos.other()),
+			rest_density,
+			viscosity: material.viscosity,
+			solver: settings.solver,
+			integrator: settings.integrator,
+			pbf: settings.pbf,
+			dfsph: settings.dfsph,
+			velocity: settings
+				.velocity
+				.unwrap_or_else(|| VelocityParams::default_for(settings.solver)),
+			alpha: Vec::new(),
+			omega: Vec::new(),
+			stats: StepStats::default(),
+			timestep: settings.timestep,
+			max_acceleration: 0.0,
+			time: 0.0,
+			removals: 0,
+			rng: Pcg32::seed_from_u64(scene.seed.unwrap_or_else(rand::random)),
+		};
+
+		for fluid in &scene.fluids {
+			for (position, velocity) in fluid.positions(material.spacing, &mut particles.rng) {
+				particles.spawn(position, velocity);
+			}
+		}
+		if particles.list.len() == particles.max_particles {
+			log::warn!(
+				"scene fluids reached the particle limit of {}",
+				particles.max_particles
+			);
+		}
+
+		particles
+	}
+
+	// adds a particle of the scene material, does nothing when full
+	pub fn spawn(&mut self, position: Vector3<f32>, velocity: Vector3<f32>) {
+		if self.list.len() >= self.max_particles {
+			return;
+		}
+		let spacing = self.material.spacing;
+		let rng = &mut self.rng;
+		let color = self
+			.material
+			.color
+			.unwrap_or_else(|| [rng.gen(), 0.8, rng.gen()]);
+		self.list.push(Particle {
+			position,
+			velocity,
+			force: Vector3::zero(),
+			radius: 0.5 * spacing,
+			mass: self.rest_density * spacing.powi(3),
+			density: 0.0,
+			pressure: 0.0,
+			color,
+		});
+	}
+
+	// whether emitters or sinks change the particle count while running
+	pub fn is_open(&self) -> bool {
+		self.emitters.iter().any(|e| e.enabled) || self.sinks.iter().any(|s| s.enabled)
+	}
+
+	// emitters add particles and sinks remove them, returns whether the count changed
+	pub fn update_flow(&mut self, dt: f32) -> bool {
+		let before = self.list.len();
+		self.emit(dt);
+		self.drain();
+		self.list.len() != before
+	}
+
+	fn emit(&mut self, dt: f32) {
+		let spacing = self.material.spacing;
+		// volume sources only fill the lattice points nothing is close to
+		let grid = self.emitters.iter().any(Emitter::fills).then(|| {
+			let mut grid = Grid::new(spacing);
+			grid.build(self.list.iter().map(|p| p.position));
+			grid
+		});
+		let list = &self.list;
+		let occupied = |x: Vector3<f32>| {
+			let mut occupied = false;
+			if let Some(grid) = &grid {
+				grid.for_each_candidate(x, |j| {
+					occupied |= (list[j].position - x).magnitude2() < 0.81 * spacing * spacing;
+				});
+			}
+			occupied
+		};
+
+		let mut spawned = Vec::new();
+		for emitter in &mut self.emitters {
+			emitter.emit(dt, spacing, occupied, &mut spawned);
+		}
+		for (position, velocity) in spawned {
+			self.spawn(position, velocity);
+		}
+	}
+
+	fn drain(&mut self) {
+		if !self.sinks.iter().any(|s| s.enabled) {
+			return;
+		}
+		let sinks = &self.sinks;
+		let before = self.list.len();
+		self.list
+			.retain(|p| !sinks.iter().any(|s| s.contains(p.position)));
+		if self.list.len() != before {
+			self.removals += 1;
+		}
+		// give memory back after a large outflow, keeping room to grow again
+		if self.list.len() < self.list.capacity() / 4 {
+			self.list.shrink_to(2 * self.list.len());
+		}
+	}
+
+	pub fn update(&mut self) {
+		let mut remaining = self.timestep.frame_time;
+		let mut substeps = 0;
+		let mut stats = StepStats::default();
+		while remaining > 0.0 {
+			if substeps == self.timestep.max_substeps {
+				log::warn!("substep budget exhausted, dropping {remaining:.3} time");
+				break;
+			}
+
+			let dt = self.stable_dt();
+			let dt = self.timestep.substep(remaining, dt);
+			stats = self.step(dt);
+			self.time += dt as f64;
+			remaining -= dt;
+			substeps += 1;
+
+			stats.dt = dt;
+		}
+		stats.substeps = substeps;
+		self.stats = stats;
+
+		log::debug!(
+			"{:?}: {} substeps (dt {:.3}), {} + {} + {} iterations, density error {:.4} (max {:.3})",
+			self.solver,
+			self.stats.substeps,
+			self.stats.dt,
+			self.stats.density_iterations,
+			self.stats.divergence_iterations,
+			self.stats.viscosity_iterations,
+			self.stats.density_error,
+			self.max_density_error()
+		);
+	}
+
+	fn stable_dt(&self) -> f32 {
+		let max_velocity = self
+			.list
+			.iter()
+			.map(|p| p.velocity.magnitude())
+			.fold(0.0, f32::max);
+		self.timestep.stable_dt(
+			self.h,
+			max_velocity,
+			self.max_acceleration,
+			self.explicit_viscosity(),
+		)
+	}
+
+	fn step(&mut self, dt: f32) -> StepStats {
+		self.emit(dt);
+		let mut stats = match self.solver {
+			Solver::StateEquation => {
+				self.integrate(dt);
+				StepStats {
+					density_error: self.avg_density_error(),
+					..StepStats::default()
+				}
+			}
+			Solver::Pbf => {
+				self.step_pbf(dt);
+				StepStats {
+					density_iterations: self.pbf.iterations,
+					density_error: self.avg_density_error(),
+					..StepStats::default()
+				}
+			}
+			Solver::Dfsph => self.step_dfsph(dt),
+		};
+		stats.viscosity_iterations = self.solve_viscosity(dt);
+		self.correct_velocities(dt);
+		// last, so nothing pushes velocities back into the colliders
+		self.resolve_colliders();
+		self.drain();
+		stats
+	}
+
+	fn resolve_colliders(&mut self) {
+		for p in &mut self.list {
+			for collider in &self.colliders {
+				collider.resolve(&mut p.position, &mut p.velocity, p.radius);
+			}
+		}
+	}
+
+	pub fn update_grid(&mut self) {
+		self.grid.build(self.list.iter().map(|p| p.position));
+	}
+
+	// calls `f(j, r_ij, r_sq)` for every particle j within h of particle i, including i itself
+	// r_ij points from i to j
+	// only valid after `update_grid`, as long as positions haven't changed
+	pub fn for_each_neighbor(&self, i: usize, mut f: impl FnMut(usize, Vector3<f32>, f32)) {
+		let position = self.list[i].position;
+		self.grid.for_each_candidate(position, |j| {
+			let r_ij = self.list[j].position - position;
+			let r_sq = r_ij.magnitude2();
+			if r_sq < self.h.powi(2) {
+				f(j, r_ij, r_sq);
+			}
+		});
+	}
+
+	// omega_i = sum_j V_j (v_j - v_i) x grad_j W_ij
+	// only valid after `update_grid`, like `for_each_neighbor`
+	pub fn vorticity(&self, i: usize) -> Vector3<f32> {
+		let p_i = self.list[i];
+		let mut omega = Vector3::zero();
+		self.for_each_neighbor(i, |j, r_ij, r_sq| {
+			if i != j && r_sq > 0.0 {
+				let p_j = self.list[j];
+				let volume = p_j.mass / p_j.density;
+				omega +=
+					(p_j.velocity - p_i.velocity).cross(-spiky_grad(r_ij, r_sq, self.h)) * volume;
+			}
+		});
+		omega
+	}
+
+	pub fn update_pressure(&mut self) {
+		let densities = (0..self.list.len())
+			.map(|i| {
+				let mut density = 0.0;
+				self.for_each_neighbor(i, |j, _, r_sq| {
+					density += self.list[j].mass * w_poly6(r_sq, self.h);
+				});
+				density
+			})
+			.collect::<Vec<_>>();
+
+		for (p, density) in self.list.iter_mut().zip(densities) {
+			p.density = density;
+			p.pressure = self.eos.pressure(density, self.rest_density);
+		}
+	}
+
+	// average relative compression, the same measure DFSPH solves for
+	pub fn avg_density_error(&self) -> f32 {
+		let sum = self
+			.list
+			.iter()
+			.map(|p| (p.density / self.rest_density - 1.0).max(0.0))
+			.sum::<f32>();
+		sum / self.list.len().max(1) as f32
+	}
+
+	// largest relative deviation from the rest density
+	pub fn max_density_error(&self) -> f32 {
+		self.list
+			.iter()
+			.map(|p| (p.density / self.rest_density - 1.0).abs())
+			.fold(0.0, f32::max)
+	}
+
+	pub fn update_forces(&mut self) {
+		let non_pressure = self.non_pressure_forces();
+
+		let forces = (0..self.list.len())
+			.map(|i| {
+				let p_i = self.list[i];
+				let mut f_press = Vector3::zero();
+				self.for_each_neighbor(i, |j, r_ij, r_sq| {
+					// coincident particles (e.g. stacked in a collider corner) have no direction
+					if i == j || r_sq == 0.0 {
+						return;
+					}
+					let p_j = self.list[j];
+
+					f_press += -r_ij.normalize()
+						* p_j.mass * (p_i.pressure + p_j.pressure)
+						* w_spiky_grad(r_sq, self.h)
+						/ (2.0 * p_j.density);
+				});
+				f_press
+			})
+			.collect::<Vec<_>>();
+
+		for (p, (f_press, f_other)) in self
+			.list
+			.iter_mut()
+			.zip(forces.into_iter().zip(non_pressure))
+		{
+			p.force += f_press + f_other;
+		}
+	}
+
+	// explicit viscosity, surface tension, adhesion and external force fields
+	fn non_pressure_forces(&mut self) -> Vec<Vector3<f32>> {
+		let mut rng = self.rng.clone();
+		let viscosity = self.viscosity_forces();
+		let surface = self.surface_forces();
+
+		let forces = (0..self.list.len())
+			.map(|i| {
+				let p_i = self.list[i];
+				let f_external = self
+					.fields
+					.iter()
+					.chain(&self.brush)
+					.map(|f| f.force(p_i.position, p_i.velocity, p_i.density, &mut rng))
+					.sum::<Vector3<f32>>();
+
+				viscosity[i] + surface[i] + f_external
+			})
+			.collect();
+
+		self.rng = rng;
+		forces
+	}
+
+	pub fn particles(&self) -> &[Particle] {
+		&self.list
+	}
+
+	pub fn particles_mut(&mut self) -> &mut [Particle] {
+		&mut self.list
+	}
+
+	pub fn colliders(&self) -> &[Collider] {
+		&self.colliders
+	}
+
+	pub fn fields(&self) -> &[ForceField] {
+		&self.fields
+	}
+
+	pub fn sinks(&self) -> &[Sink] {
+		&self.sinks
+	}
+
+	pub fn brush(&self) -> Option<&ForceField> {
+		self.brush.as_ref()
+	}
+
+	pub fn set_brush(&mut self, brush: Option<Field>) {
+		self.brush = brush.map(ForceField::new);
+	}
+
+	pub fn eos(&self) -> EquationOfState {
+		self.eos
+	}
+
+	pub fn rest_density(&self) -> f32 {
+		self.rest_density
+	}
+
+	pub fn viscosity(&self) -> Viscosity {
+		self.viscosity
+	}
+
+	pub fn h(&self) -> f32 {
+		self.h
+	}
+
+	pub fn max_particles(&self) -> usize {
+		self.max_particles
+	}
+
+	pub fn timestep(&self) -> &TimeStep {
+		&self.timestep
+	}
+
+	pub fn stats(&self) -> StepStats {
+		self.stats
+	}
+
+	pub fn time(&self) -> f64 {
+		self.time
+	}
+
+	pub fn solver(&self) -> Solver {
+		self.solver
+	}
+
+	pub fn set_solver(&mut self, solver: Solver) {
+		self.solver = solver;
+	}
+
+	pub fn integrator(&self) -> Integrator {
+		self.integrator
+	}
+
+	pub fn set_integrator(&mut self, integrator: Integrator) {
+		self.integrator = integrator;
+	}
+
+	pub fn set_eos(&mut self, eos: EquationOfState) {
+		self.eos = eos;
+	}
+
+	// particle masses are kept, so this compresses or expands the fluid
+	pub fn set_rest_density(&mut self, rest_density: f32) {
+		self.rest_density = rest_density;
+	}
+
+	pub fn set_viscosity(&mut self, viscosity: Viscosity) {
+		self.viscosity = viscosity;
+	}
+
+	pub fn set_h(&mut self, h: f32) {
+		self.h = h;
+		self.grid = Grid::new(h);
+	}
+
+	pub fn velocity_params(&self) -> VelocityParams {
+		self.velocity
+	}
+
+	pub fn set_velocity_params(&mut self, velocity: VelocityParams) {
+		self.velocity = velocity;
+	}
+
+	pub fn timestep_mut(&mut self) -> &mut TimeStep {
+		&mut self.timestep
+	}
+
+	pub fn toggle_solver(&mut self) {
+		self.solver = match self.solver {
+			Solver::StateEquation => Solver::Pbf,
+			Solver::Pbf => Solver::Dfsph,
+			Solver::Dfsph => Solver::StateEquation,
+		};
+		log::info!("solver: {:?}", self.solver);
+	}
+
+	pub fn toggle_integrator(&mut self) {
+		self.integrator = match self.integrator {
+			Integrator::SymplecticEuler => Integrator::Leapfrog,
+			Integrator::Leapfrog => Integrator::Rk4,
+			Integrator::Rk4 => Integrator::SymplecticEuler,
+		};
+		log::info!("integrator: {}", self.integrator.name());
+	}
+
+	pub fn toggle_eos(&mut self) {
+		std::mem::swap(&mut self.eos, &mut self.other_eos);
+		log::info!("equation of state: {}", self.eos.name());
+	}
+
+	pub fn toggle_adaptive(&mut self) {
+		self.timestep.adaptive = !self.timestep.adaptive;
+		log::info!("adaptive time step: {}", self.timestep.adaptive);
+	}
+
+	pub fn toggle_field(&mut self, index: usize) {
+		if let Some(f) = self.fields.get_mut(index) {
+			f.enabled = !f.enabled;
+			log::info!("{} field: {}", f.name(), f.enabled);
+		}
+	}
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Particle {
+	pub position: Vector3<f32>,
+	pub velocity: Vector3<f32>,
+	pub force: Vector3<f32>,
+	pub radius: f32,
+	pub mass: f32,
+	pub density: f32,
+	pub pressure: f32,
+	pub color: [f32; 3],
+}
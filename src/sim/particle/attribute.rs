@@ -0,0 +1,75 @@
+use super::Particles;
+use cgmath::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Per particle scalars that particles can be colored by.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Attribute {
+	Speed,
+	Density,
+	Pressure,
+	// signed relative deviation from the rest density
+	DensityError,
+	// magnitude of the curl of the velocity
+	Vorticity,
+	// particles within h, including the particle itself
+	Neighbors,
+}
+
+impl Attribute {
+	pub const ALL: [Attribute; 6] = [
+		Attribute::Speed,
+		Attribute::Density,
+		Attribute::Pressure,
+		Attribute::DensityError,
+		Attribute::Vorticity,
+		Attribute::Neighbors,
+	];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			Attribute::Speed => "speed",
+			Attribute::Density => "density",
+			Attribute::Pressure => "pressure",
+			Attribute::DensityError => "density error",
+			Attribute::Vorticity => "vorticity",
+			Attribute::Neighbors => "neighbor count",
+		}
+	}
+}
+
+impl Particles {
+	// one value per particle, densities and pressures are the ones of the last step
+	pub fn attribute(&mut self, attribute: Attribute) -> Vec<f32> {
+		match attribute {
+			Attribute::Speed => self.list.iter().map(|p| p.velocity.magnitude()).collect(),
+			Attribute::Density => self.list.iter().map(|p| p.density).collect(),
+			Attribute::Pressure => self.list.iter().map(|p| p.pressure).collect(),
+			Attribute::DensityError => self
+				.list
+				.iter()
+				.map(|p| p.density / self.rest_density - 1.0)
+				.collect(),
+			Attribute::Vorticity => {
+				// left behind by vorticity confinement, otherwise computed from the current velocities
+				if self.omega.len() == self.list.len() {
+					return self.omega.iter().map(|w| w.magnitude()).collect();
+				}
+				self.update_grid();
+				(0..self.list.len())
+					.map(|i| self.vorticity(i).magnitude())
+					.collect()
+			}
+			Attribute::Neighbors => {
+				self.update_grid();
+				(0..self.list.len())
+					.map(|i| {
+						let mut count = 0;
+						self.for_each_neighbor(i, |_, _, _| count += 1);
+						count as f32
+					})
+					.collect()
+			}
+		}
+	}
+}
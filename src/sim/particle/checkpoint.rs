@@ -0,0 +1,143 @@
+// Binary snapshots of the full simulation state.
+// A checkpoint is the magic bytes and a little endian format version, then a bincode body.
+// Bump VERSION whenever `Checkpoint` changes, old files are rejected instead of misread.
+use super::{
+	DfsphParams, Integrator, Particle, Particles, PbfParams, Solver, StepStats, VelocityParams,
+	Viscosity,
+};
+use crate::sim::collider::Collider;
+use crate::sim::emitter::Emitter;
+use crate::sim::eos::EquationOfState;
+use crate::sim::force::ForceField;
+use crate::sim::grid::Grid;
+use crate::sim::scene::Material;
+use crate::sim::sink::Sink;
+use crate::sim::timestep::TimeStep;
+use anyhow::{bail, Context};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::Path;
+
+const MAGIC: [u8; 4] = *b"WFCK";
+const VERSION: u32 = 7;
+
+// everything in `Particles` except what is rebuilt every step
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+	time: f64,
+	rng: Pcg32,
+	list: Vec<Particle>,
+	max_particles: usize,
+	h: f32,
+	material: Material,
+	colliders: Vec<Collider>,
+	fields: Vec<ForceField>,
+	emitters: Vec<Emitter>,
+	sinks: Vec<Sink>,
+	eos: EquationOfState,
+	other_eos: EquationOfState,
+	rest_density: f32,
+	viscosity: Viscosity,
+	solver: Solver,
+	integrator: Integrator,
+	pbf: PbfParams,
+	dfsph: DfsphParams,
+	velocity: VelocityParams,
+	timestep: TimeStep,
+	max_acceleration: f32,
+}
+
+impl Particles {
+	pub fn save(&self, mut writer: impl Write) -> anyhow::Result<()> {
+		let checkpoint = Checkpoint {
+			time: self.time,
+			rng: self.rng.clone(),
+			list: self.list.clone(),
+			max_particles: self.max_particles,
+			h: self.h,
+			material: self.material,
+			colliders: self.colliders.clone(),
+			fields: self.fields.clone(),
+			emitters: self.emitters.clone(),
+			sinks: self.sinks.clone(),
+			eos: self.eos,
+			other_eos: self.other_eos,
+			rest_density: self.rest_density,
+			viscosity: self.viscosity,
+			solver: self.solver,
+			integrator: self.integrator,
+			pbf: self.pbf,
+			dfsph: self.dfsph,
+			velocity: self.velocity,
+			timestep: self.timestep,
+			max_acceleration: self.max_acceleration,
+		};
+
+		writer.write_all(&MAGIC)?;
+		writer.write_all(&VERSION.to_le_bytes())?;
+		bincode::serialize_into(&mut writer, &checkpoint)?;
+		writer.flush()?;
+		Ok(())
+	}
+
+	pub fn load(mut reader: impl Read) -> anyhow::Result<Self> {
+		let mut magic = [0; 4];
+		reader
+			.read_exact(&mut magic)
+			.context("checkpoint is too short")?;
+		if magic != MAGIC {
+			bail!("not a checkpoint file");
+		}
+		let mut version = [0; 4];
+		reader.read_exact(&mut version)?;
+		let version = u32::from_le_bytes(version);
+		if version != VERSION {
+			bail!("checkpoint version {version} is not supported, expected {VERSION}");
+		}
+
+		let c: Checkpoint = bincode::deserialize_from(reader).context("corrupt checkpoint")?;
+		Ok(Self {
+			list: c.list,
+			max_particles: c.max_particles,
+			grid: Grid::new(c.h),
+			h: c.h,
+			material: c.material,
+			colliders: c.colliders,
+			fields: c.fields,
+			brush: None,
+			emitters: c.emitters,
+			sinks: c.sinks,
+			eos: c.eos,
+			other_eos: c.other_eos,
+			rest_density: c.rest_density,
+			viscosity: c.viscosity,
+			solver: c.solver,
+			integrator: c.integrator,
+			pbf: c.pbf,
+			dfsph: c.dfsph,
+			velocity: c.velocity,
+			alpha: Vec::new(),
+			omega: Vec::new(),
+			stats: StepStats::default(),
+			timestep: c.timestep,
+			max_acceleration: c.max_acceleration,
+			time: c.time,
+			removals: 0,
+			rng: c.rng,
+		})
+	}
+
+	pub fn save_file(&self, path: &Path) -> anyhow::Result<()> {
+		let file = std::fs::File::create(path)
+			.with_context(|| format!("could not create {}", path.display()))?;
+		self.save(std::io::BufWriter::new(file))
+	}
+
+	pub fn load_file(path: &Path) -> anyhow::Result<Self> {
+		let file = std::fs::File::open(path)
+			.with_context(|| format!("could not open {}", path.display()))?;
+		Self::load(std::io::BufReader::new(file))
+			.with_context(|| format!("could not load {}", path.display()))
+	}
+}
@@ -0,0 +1,195 @@
+// Divergence-free SPH, Bender & Koschier 2015
+use super::{spiky_grad, w_poly6, Particles, StepStats};
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DfsphParams {
+	// average relative density error the constant density solve stops at
+	pub density_tolerance: f32,
+	// average relative density change per step the divergence solve stops at
+	pub divergence_tolerance: f32,
+	pub min_iterations: usize,
+	pub max_iterations: usize,
+	pub max_divergence_iterations: usize,
+}
+
+impl Default for DfsphParams {
+	fn default() -> Self {
+		Self {
+			density_tolerance: 0.001,
+			divergence_tolerance: 0.01,
+			min_iterations: 2,
+			max_iterations: 100,
+			max_divergence_iterations: 100,
+		}
+	}
+}
+
+impl Particles {
+	pub(super) fn step_dfsph(&mut self, dt: f32) -> StepStats {
+		let params = self.dfsph;
+
+		self.update_grid();
+		self.update_density_and_alpha();
+
+		let divergence_iterations = self.correct_divergence_error(&params, dt);
+
+		// non-pressure forces predict the velocity
+		let forces = self.non_pressure_forces();
+		self.max_acceleration = 0.0;
+		for (p, f) in self.list.iter_mut().zip(forces) {
+			let acceleration = f / p.density;
+			self.max_acceleration = self.max_acceleration.max(acceleration.magnitude());
+			p.velocity += acceleration * dt;
+			p.force = Vector3::zero();
+		}
+
+		let (density_iterations, density_error) = self.correct_density_error(&params, dt);
+
+		for p in &mut self.list {
+			p.position += p.velocity * dt;
+		}
+
+		StepStats {
+			density_iterations,
+			divergence_iterations,
+			density_error,
+			..StepStats::default()
+		}
+	}
+
+	// densities and the DFSPH factor alpha_i = rho_i / (|sum_j m_j grad W_ij|^2 + sum_j |m_j grad W_ij|^2)
+	fn update_density_and_alpha(&mut self) {
+		let h = self.h;
+		let values = (0..self.list.len())
+			.map(|i| {
+				let mut density = 0.0;
+				let mut grad_i = Vector3::zero();
+				let mut grad_sq = 0.0;
+				self.for_each_neighbor(i, |j, r_ij, r_sq| {
+					let m_j = self.list[j].mass;
+					density += m_j * w_poly6(r_sq, h);
+					if i != j && r_sq > 0.0 {
+						let grad = spiky_grad(r_ij, r_sq, h) * m_j;
+						grad_i += grad;
+						grad_sq += grad.magnitude2();
+					}
+				});
+				let denom = grad_i.magnitude2() + grad_sq;
+				let alpha = if denom > 1e-12 { density / denom } else { 0.0 };
+				(density, alpha)
+			})
+			.collect::<Vec<_>>();
+
+		self.alpha.clear();
+		for (p, (density, alpha)) in self.list.iter_mut().zip(values) {
+			p.density = density;
+			p.pressure = 0.0;
+			self.alpha.push(alpha);
+		}
+	}
+
+	// D rho_i / Dt = sum_j m_j (v_i - v_j) . grad W_ij
+	fn density_change(&self, i: usize) -> f32 {
+		let h = self.h;
+		let p_i = self.list[i];
+		let mut change = 0.0;
+		self.for_each_neighbor(i, |j, r_ij, r_sq| {
+			if i != j && r_sq > 0.0 {
+				let p_j = self.list[j];
+				change += p_j.mass * (p_i.velocity - p_j.velocity).dot(spiky_grad(r_ij, r_sq, h));
+			}
+		});
+		change
+	}
+
+	// v_i -= dt sum_j m_j (kappa_i / rho_i + kappa_j / rho_j) grad W_ij
+	fn apply_kappa(&mut self, kappa: &[f32], dt: f32) {
+		let h = self.h;
+		let corrections = (0..self.list.len())
+			.map(|i| {
+				let k_i = kappa[i] / self.list[i].density;
+				let mut dv = Vector3::zero();
+				self.for_each_neighbor(i, |j, r_ij, r_sq| {
+					if i != j && r_sq > 0.0 {
+						let p_j = self.list[j];
+						dv +=
+							spiky_grad(r_ij, r_sq, h) * (p_j.mass * (k_i + kappa[j] / p_j.density));
+					}
+				});
+				dv * dt
+			})
+			.collect::<Vec<_>>();
+
+		for (p, dv) in self.list.iter_mut().zip(corrections) {
+			p.velocity -= dv;
+		}
+	}
+
+	// makes the velocity field divergence free, only compression is corrected
+	fn correct_divergence_error(&mut self, params: &DfsphParams, dt: f32) -> usize {
+		let n = self.list.len();
+		if n == 0 {
+			return 0;
+		}
+
+		let mut iterations = 0;
+		while iterations < params.max_divergence_iterations {
+			let change = (0..n)
+				.map(|i| self.density_change(i).max(0.0))
+				.collect::<Vec<_>>();
+
+			let avg = change.iter().sum::<f32>() / n as f32;
+			if iterations > 0 && avg * dt <= params.divergence_tolerance * self.rest_density {
+				break;
+			}
+
+			let kappa = change
+				.iter()
+				.zip(&self.alpha)
+				.map(|(c, alpha)| c / dt * alpha)
+				.collect::<Vec<_>>();
+			self.apply_kappa(&kappa, dt);
+			iterations += 1;
+		}
+		iterations
+	}
+
+	// corrects the predicted density toward rest density, returns iterations and the final average error
+	fn correct_density_error(&mut self, params: &DfsphParams, dt: f32) -> (usize, f32) {
+		let n = self.list.len();
+		if n == 0 {
+			return (0, 0.0);
+		}
+
+		let mut iterations = 0;
+		let avg_error = loop {
+			// predicted density error after advecting with the current velocities
+			let error = (0..n)
+				.map(|i| {
+					(self.list[i].density + dt * self.density_change(i) - self.rest_density)
+						.max(0.0)
+				})
+				.collect::<Vec<_>>();
+
+			let avg_error = error.iter().sum::<f32>() / n as f32 / self.rest_density;
+			if (iterations >= params.min_iterations && avg_error <= params.density_tolerance)
+				|| iterations >= params.max_iterations
+			{
+				break avg_error;
+			}
+
+			let kappa = error
+				.iter()
+				.zip(&self.alpha)
+				.map(|(e, alpha)| e / dt.powi(2) * alpha)
+				.collect::<Vec<_>>();
+			self.apply_kappa(&kappa, dt);
+			iterations += 1;
+		};
+		(iterations, avg_error)
+	}
+}
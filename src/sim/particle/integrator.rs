@@ -0,0 +1,106 @@
+// Time integration of the state equation solver.
+// PBF and DFSPH have their own position and velocity updates.
+// Colliders are resolved at the end of every step, after the velocity passes.
+use super::Particles;
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Integrator {
+	// v += a dt, then x += v dt
+	SymplecticEuler,
+	// kick-drift-kick, two force evaluations per step
+	Leapfrog,
+	// classic fourth order Runge-Kutta, four force evaluations per step
+	Rk4,
+}
+
+impl Integrator {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::SymplecticEuler => "symplectic euler",
+			Self::Leapfrog => "leapfrog",
+			Self::Rk4 => "rk4",
+		}
+	}
+}
+
+impl Particles {
+	pub(super) fn integrate(&mut self, dt: f32) {
+		let a = self.accelerations();
+		self.max_acceleration = a.iter().map(|a| a.magnitude()).fold(0.0, f32::max);
+
+		match self.integrator {
+			Integrator::SymplecticEuler => {
+				self.kick(&a, dt);
+				self.drift(dt);
+			}
+			Integrator::Leapfrog => {
+				self.kick(&a, 0.5 * dt);
+				self.drift(dt);
+				// the second force evaluation needs the particles out of the colliders
+				self.resolve_colliders();
+				let a = self.accelerations();
+				self.kick(&a, 0.5 * dt);
+			}
+			Integrator::Rk4 => self.rk4(a, dt),
+		}
+	}
+
+	// SPH forces at the current state, divided by density
+	fn accelerations(&mut self) -> Vec<Vector3<f32>> {
+		self.update_grid();
+		self.update_pressure();
+		self.update_forces();
+		self.list
+			.iter_mut()
+			.map(|p| {
+				let a = p.force / p.density;
+				p.force = Vector3::zero();
+				a
+			})
+			.collect()
+	}
+
+	fn kick(&mut self, a: &[Vector3<f32>], dt: f32) {
+		for (p, a) in self.list.iter_mut().zip(a) {
+			p.velocity += a * dt;
+		}
+	}
+
+	fn drift(&mut self, dt: f32) {
+		for p in &mut self.list {
+			p.position += p.velocity * dt;
+		}
+	}
+
+	// the stages evaluate the forces at trial states, colliders only act on the final one
+	fn rk4(&mut self, a1: Vec<Vector3<f32>>, dt: f32) {
+		let x0 = self.list.iter().map(|p| p.position).collect::<Vec<_>>();
+		let v0 = self.list.iter().map(|p| p.velocity).collect::<Vec<_>>();
+
+		let mut dx = v0.clone();
+		let mut dv = a1.clone();
+		let mut k_x = v0.clone();
+		let mut k_v = a1;
+		for (h, weight) in [(0.5 * dt, 2.0), (0.5 * dt, 2.0), (dt, 1.0)] {
+			// trial state x0 + h k, v0 + h k from the previous stage
+			for (i, p) in self.list.iter_mut().enumerate() {
+				p.position = x0[i] + k_x[i] * h;
+				p.velocity = v0[i] + k_v[i] * h;
+			}
+			k_x = self.list.iter().map(|p| p.velocity).collect();
+			k_v = self.accelerations();
+			for i in 0..self.list.len() {
+				dx[i] += k_x[i] * weight;
+				dv[i] += k_v[i] * weight;
+			}
+		}
+
+		for (i, p) in self.list.iter_mut().enumerate() {
+			p.position = x0[i] + dx[i] * (dt / 6.0);
+			p.velocity = v0[i] + dv[i] * (dt / 6.0);
+		}
+	}
+}
@@ -0,0 +1,122 @@
+use super::Particles;
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+// World space ray from the cursor through the scene.
+#[derive(Debug, Copy, Clone)]
+pub struct Ray {
+	pub origin: Vector3<f32>,
+	// unit length
+	pub direction: Vector3<f32>,
+}
+
+impl Ray {
+	pub fn new(origin: Vector3<f32>, direction: Vector3<f32>) -> Self {
+		Self {
+			origin,
+			direction: direction.normalize(),
+		}
+	}
+
+	pub fn at(&self, t: f32) -> Vector3<f32> {
+		self.origin + t * self.direction
+	}
+}
+
+// Particles held by the cursor, they keep their offsets from the grab point while dragged.
+#[derive(Debug, Clone)]
+pub struct Grab {
+	indices: Vec<usize>,
+	offsets: Vec<Vector3<f32>>,
+	// along the ray, the grab point stays at this distance from the camera
+	pub distance: f32,
+	// where the grabbed particles are headed, and how far around it they were taken from
+	center: Vector3<f32>,
+	radius: f32,
+	// `Particles::removals` when the indices were taken
+	removals: u64,
+}
+
+impl Particles {
+	// distance along the ray to the center of the first particle it passes through
+	pub fn pick(&self, ray: &Ray) -> Option<f32> {
+		self.list
+			.iter()
+			.filter_map(|p| {
+				let t = (p.position - ray.origin).dot(ray.direction);
+				let miss = (ray.at(t) - p.position).magnitude2();
+				(t > 0.0 && miss <= p.radius * p.radius).then_some(t)
+			})
+			.min_by(f32::total_cmp)
+	}
+
+	// the particles within `radius` of the first one on the ray
+	pub fn grab(&self, ray: &Ray, radius: f32) -> Option<Grab> {
+		let distance = self.pick(ray)?;
+		Some(self.grab_around(ray.at(distance), radius, distance))
+	}
+
+	fn grab_around(&self, center: Vector3<f32>, radius: f32, distance: f32) -> Grab {
+		let (indices, offsets) = self
+			.list
+			.iter()
+			.enumerate()
+			.filter(|(_, p)| (p.position - center).magnitude2() < radius * radius)
+			.map(|(i, p)| (i, p.position - center))
+			.unzip();
+		Grab {
+			indices,
+			offsets,
+			distance,
+			center,
+			radius,
+			removals: self.removals,
+		}
+	}
+
+	// sets the velocities that carry the grabbed particles to the grab point on `ray` in one step
+	pub fn drag(&mut self, grab: &mut Grab, ray: &Ray) {
+		// sinks or the eraser moved particles into the grabbed indices,
+		// take the particles around where the grabbed ones were sent instead
+		if grab.removals != self.removals {
+			*grab = self.grab_around(grab.center, grab.radius, grab.distance);
+		}
+		let center = ray.at(grab.distance);
+		let dt = self.timestep.frame_time;
+		for (&i, offset) in grab.indices.iter().zip(&grab.offsets) {
+			let p = &mut self.list[i];
+			p.velocity = (center + offset - p.position) / dt;
+		}
+		grab.center = center;
+	}
+
+	// fills a ball with particles on the material spacing, returns how many fit under the limit
+	pub fn spawn_ball(&mut self, center: Vector3<f32>, radius: f32) -> usize {
+		let spacing = self.material.spacing;
+		let steps = (radius / spacing) as i32;
+		let before = self.list.len();
+		for x in -steps..=steps {
+			for y in -steps..=steps {
+				for z in -steps..=steps {
+					let offset = Vector3::new(x as f32, y as f32, z as f32) * spacing;
+					if offset.magnitude() <= radius {
+						self.spawn(center + offset, Vector3::zero());
+					}
+				}
+			}
+		}
+		self.list.len() - before
+	}
+
+	// removes the particles within `radius` of `center`, returns how many
+	pub fn erase(&mut self, center: Vector3<f32>, radius: f32) -> usize {
+		let before = self.list.len();
+		self.list
+			.retain(|p| (p.position - center).magnitude2() >= radius * radius);
+		let erased = before - self.list.len();
+		if erased > 0 {
+			self.removals += 1;
+		}
+		erased
+	}
+}
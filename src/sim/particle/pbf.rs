@@ -0,0 +1,142 @@
+// Position Based Fluids, Macklin & Müller 2013
+use super::{spiky_grad, w_poly6, Particles};
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+
+// xsph and vorticity used to be here and are `SolverSettings::velocity` now,
+// scenes that still set them fail to load instead of losing them
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PbfParams {
+	pub iterations: usize,
+	// constraint force mixing, keeps lambda bounded for particles with few neighbors
+	pub relaxation: f32,
+	// artificial pressure against tensile instability, s_corr = -k (W(r) / W(dq))^n
+	pub s_corr_k: f32,
+	pub s_corr_n: i32,
+	// as a fraction of h
+	pub s_corr_dq: f32,
+}
+
+impl Default for PbfParams {
+	fn default() -> Self {
+		Self {
+			iterations: 4,
+			relaxation: 0.01,
+			s_corr_k: 0.1,
+			s_corr_n: 4,
+			s_corr_dq: 0.2,
+		}
+	}
+}
+
+impl Particles {
+	pub(super) fn step_pbf(&mut self, dt: f32) {
+		let h = self.h;
+		let params = self.pbf;
+		let rest_density = self.rest_density;
+		let n = self.list.len();
+
+		// viscosity, surface tension and adhesion need the densities at the start of the step
+		let has_viscosity = self.explicit_viscosity() != 0.0;
+		let internal = if has_viscosity || self.has_surface_forces() {
+			self.update_grid();
+			self.update_pressure();
+			let mut forces = self.viscosity_forces();
+			if self.has_surface_forces() {
+				for (f, f_surface) in forces.iter_mut().zip(self.surface_forces()) {
+					*f += f_surface;
+				}
+			}
+			forces
+		} else {
+			vec![Vector3::zero(); n]
+		};
+
+		// external forces, then predict positions
+		// like in DFSPH the force time step limit leaves out the density constraint,
+		// its corrections grow as 1 / dt^2 and would keep shrinking the step
+		let previous = self.list.iter().map(|p| p.position).collect::<Vec<_>>();
+		self.max_acceleration = 0.0;
+		for (p, f_internal) in self.list.iter_mut().zip(internal) {
+			let force = self
+				.fields
+				.iter()
+				.chain(&self.brush)
+				.map(|f| f.force(p.position, p.velocity, rest_density, &mut self.rng))
+				.sum::<Vector3<f32>>()
+				+ f_internal;
+			let acceleration = force / rest_density;
+			self.max_acceleration = self.max_acceleration.max(acceleration.magnitude());
+			p.velocity += acceleration * dt;
+			p.position += p.velocity * dt;
+			p.force = Vector3::zero();
+		}
+
+		let w_dq = w_poly6((params.s_corr_dq * h).powi(2), h);
+
+		for _ in 0..params.iterations {
+			// positions move every iteration, the grid is cheap enough to rebuild
+			self.update_grid();
+
+			// density constraint C_i = rho_i / rho_0 - 1
+			let lambdas = (0..n)
+				.map(|i| {
+					let mut density = 0.0;
+					let mut grad_i = Vector3::zero();
+					let mut grad_sq = 0.0;
+					self.for_each_neighbor(i, |j, r_ij, r_sq| {
+						let m_j = self.list[j].mass;
+						density += m_j * w_poly6(r_sq, h);
+						if i != j && r_sq > 0.0 {
+							let grad = spiky_grad(r_ij, r_sq, h) * (m_j / rest_density);
+							grad_i += grad;
+							grad_sq += grad.magnitude2();
+						}
+					});
+					let c = density / rest_density - 1.0;
+					let lambda = -c / (grad_sq + grad_i.magnitude2() + params.relaxation);
+					(density, lambda)
+				})
+				.collect::<Vec<_>>();
+
+			let corrections = (0..n)
+				.map(|i| {
+					let lambda_i = lambdas[i].1;
+					let mut dp = Vector3::zero();
+					self.for_each_neighbor(i, |j, r_ij, r_sq| {
+						if i != j && r_sq > 0.0 {
+							let s_corr =
+								-params.s_corr_k * (w_poly6(r_sq, h) / w_dq).powi(params.s_corr_n);
+							dp += spiky_grad(r_ij, r_sq, h)
+								* (self.list[j].mass * (lambda_i + lambdas[j].1 + s_corr));
+						}
+					});
+					dp / rest_density
+				})
+				.collect::<Vec<_>>();
+
+			for (p, (dp, &(density, _))) in self
+				.list
+				.iter_mut()
+				.zip(corrections.iter().zip(lambdas.iter()))
+			{
+				p.position += *dp;
+				p.density = density;
+				p.pressure = 0.0;
+
+				// only project positions here, the velocity response happens below
+				let mut velocity = p.velocity;
+				for collider in &self.colliders {
+					collider.resolve(&mut p.position, &mut velocity, p.radius);
+				}
+			}
+		}
+
+		// the colliders act on the velocities after the velocity passes, see `step`
+		for (p, x) in self.list.iter_mut().zip(previous) {
+			p.velocity = (p.position - x) / dt;
+		}
+	}
+}
@@ -0,0 +1,158 @@
+// Surface tension and adhesion to colliders
+use super::{spiky_grad, w_poly6_grad, w_poly6_lap, Particles};
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum SurfaceTension {
+	#[default]
+	None,
+	// cohesion between neighbors plus curvature minimization, Akinci et al. 2013
+	// `coefficient` is an acceleration, like the one of gravity
+	Akinci {
+		coefficient: f32,
+	},
+	// continuum surface force from the color field, Müller et al. 2003
+	// only acts where |grad c| h is above `threshold`, which keeps noise inside the fluid out
+	Csf {
+		coefficient: f32,
+		threshold: f32,
+	},
+}
+
+impl SurfaceTension {
+	pub fn name(self) -> &'static str {
+		match self {
+			SurfaceTension::None => "none",
+			SurfaceTension::Akinci { .. } => "akinci",
+			SurfaceTension::Csf { .. } => "csf",
+		}
+	}
+}
+
+// Akinci's cohesion kernel scaled to peak at 1 for r = h/2, attracts at mid range and repels up close
+fn cohesion(r: f32, h: f32) -> f32 {
+	let c = 64.0 / h.powi(6);
+	if 2.0 * r > h && r <= h {
+		c * (h - r).powi(3) * r.powi(3)
+	} else if r > 0.0 && 2.0 * r <= h {
+		c * (2.0 * (h - r).powi(3) * r.powi(3) - h.powi(6) / 64.0)
+	} else {
+		0.0
+	}
+}
+
+// Akinci's adhesion kernel, scaled to peak at 1 for r = 3h/4
+fn adhesion(r: f32, h: f32) -> f32 {
+	if 2.0 * r > h && r <= h {
+		let peak = (0.25 * h).powf(0.25);
+		(-4.0 * r * r / h + 6.0 * r - 2.0 * h).max(0.0).powf(0.25) / peak
+	} else {
+		0.0
+	}
+}
+
+impl Particles {
+	pub(super) fn has_surface_forces(&self) -> bool {
+		self.material.surface_tension != SurfaceTension::None || self.material.adhesion != 0.0
+	}
+
+	// surface tension and adhesion per unit volume, like the other forces
+	// only valid after `update_grid` and with current densities
+	pub(super) fn surface_forces(&self) -> Vec<Vector3<f32>> {
+		let mut forces = match self.material.surface_tension {
+			SurfaceTension::None => vec![Vector3::zero(); self.list.len()],
+			SurfaceTension::Akinci { coefficient } => self.akinci(coefficient),
+			SurfaceTension::Csf {
+				coefficient,
+				threshold,
+			} => self.csf(coefficient, threshold),
+		};
+		if self.material.adhesion != 0.0 {
+			for (f, a) in forces.iter_mut().zip(self.adhesion(self.material.adhesion)) {
+				*f += a;
+			}
+		}
+		forces
+	}
+
+	// n_i = h sum_j V_j grad W_ij, pointing into the fluid and longest at the surface
+	fn surface_normals(&self) -> Vec<Vector3<f32>> {
+		(0..self.list.len())
+			.map(|i| {
+				let mut n = Vector3::zero();
+				self.for_each_neighbor(i, |j, r_ij, r_sq| {
+					if i != j && r_sq > 0.0 {
+						let p_j = self.list[j];
+						n += spiky_grad(r_ij, r_sq, self.h) * (p_j.mass / p_j.density);
+					}
+				});
+				n * self.h
+			})
+			.collect()
+	}
+
+	fn akinci(&self, gamma: f32) -> Vec<Vector3<f32>> {
+		let normals = self.surface_normals();
+		(0..self.list.len())
+			.map(|i| {
+				let p_i = self.list[i];
+				let mut a = Vector3::zero();
+				self.for_each_neighbor(i, |j, r_ij, r_sq| {
+					if i == j || r_sq == 0.0 {
+						return;
+					}
+					let p_j = self.list[j];
+					let r = r_sq.sqrt();
+					// pulls particles with too few neighbors harder, mostly the ones at the surface
+					let correction = 2.0 * self.rest_density / (p_i.density + p_j.density);
+					let cohesion = r_ij * (cohesion(r, self.h) / r);
+					let curvature = normals[j] - normals[i];
+					a += (cohesion + curvature) * correction;
+				});
+				a * (gamma * p_i.density)
+			})
+			.collect()
+	}
+
+	fn csf(&self, sigma: f32, threshold: f32) -> Vec<Vector3<f32>> {
+		(0..self.list.len())
+			.map(|i| {
+				// gradient and laplacian of the color field, 1 in the fluid and 0 outside
+				let mut n = Vector3::zero();
+				let mut laplacian = 0.0;
+				self.for_each_neighbor(i, |j, r_ij, r_sq| {
+					let p_j = self.list[j];
+					let volume = p_j.mass / p_j.density;
+					n += r_ij * (volume * w_poly6_grad(r_sq, self.h));
+					laplacian += volume * w_poly6_lap(r_sq, self.h);
+				});
+				let len = n.magnitude();
+				if len * self.h > threshold {
+					-sigma * laplacian * n / len
+				} else {
+					Vector3::zero()
+				}
+			})
+			.collect()
+	}
+
+	// pulls particles within h toward collider surfaces
+	fn adhesion(&self, beta: f32) -> Vec<Vector3<f32>> {
+		self.list
+			.iter()
+			.map(|p| {
+				self.colliders
+					.iter()
+					.map(|c| {
+						let (distance, normal) = c.distance(p.position);
+						// colliders keep particles a radius away from the surface,
+						// boundary samples on the surface would keep them a spacing away
+						-normal * (beta * p.density * adhesion(distance + p.radius, self.h))
+					})
+					.sum::<Vector3<f32>>()
+			})
+			.collect()
+	}
+}
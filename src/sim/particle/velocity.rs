@@ -0,0 +1,105 @@
+// Velocity passes after every step of any solver
+use super::{spiky_grad, w_poly6, Particles, Solver};
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VelocityParams {
+	// vorticity confinement strength, adds back small scale rotation lost to numerical damping
+	// Fedkiw et al. 2001, off at 0
+	pub vorticity: f32,
+	// fraction of the way each velocity is blended toward its neighborhood average, off at 0
+	pub xsph: f32,
+}
+
+impl VelocityParams {
+	// for scenes that don't set them, PBF keeps the passes it used to apply itself
+	pub fn default_for(solver: Solver) -> Self {
+		match solver {
+			Solver::Pbf => Self {
+				vorticity: 0.0001,
+				xsph: 0.01,
+			},
+			Solver::StateEquation | Solver::Dfsph => Self::default(),
+		}
+	}
+}
+
+impl Particles {
+	pub(super) fn correct_velocities(&mut self, dt: f32) {
+		let params = self.velocity;
+		if params.vorticity == 0.0 && params.xsph == 0.0 {
+			self.omega.clear();
+			return;
+		}
+
+		self.update_grid();
+		if params.vorticity > 0.0 {
+			self.vorticity_confinement(params.vorticity, dt);
+		} else {
+			self.omega.clear();
+		}
+		if params.xsph > 0.0 {
+			self.xsph(params.xsph);
+		}
+	}
+
+	fn vorticity_confinement(&mut self, epsilon: f32, dt: f32) {
+		let h = self.h;
+		let n = self.list.len();
+
+		// kept for coloring by vorticity
+		self.omega = (0..n).map(|i| self.vorticity(i)).collect();
+		let omega = &self.omega;
+
+		let forces = (0..n)
+			.map(|i| {
+				// eta = grad |omega|, pointing toward higher vorticity
+				let mut eta = Vector3::zero();
+				self.for_each_neighbor(i, |j, r_ij, r_sq| {
+					if i != j && r_sq > 0.0 {
+						let p_j = self.list[j];
+						let volume = p_j.mass / p_j.density;
+						eta += spiky_grad(r_ij, r_sq, h)
+							* (volume * (omega[j].magnitude() - omega[i].magnitude()));
+					}
+				});
+				let len = eta.magnitude();
+				if len > 1e-9 {
+					epsilon * (eta / len).cross(omega[i])
+				} else {
+					Vector3::zero()
+				}
+			})
+			.collect::<Vec<_>>();
+
+		for (p, f) in self.list.iter_mut().zip(forces) {
+			p.velocity += f * dt;
+		}
+	}
+
+	// blends each velocity toward its neighborhood average
+	fn xsph(&mut self, c: f32) {
+		let h = self.h;
+		let n = self.list.len();
+
+		let corrections = (0..n)
+			.map(|i| {
+				let p_i = self.list[i];
+				let mut dv = Vector3::zero();
+				self.for_each_neighbor(i, |j, _, r_sq| {
+					let p_j = self.list[j];
+					dv +=
+						(p_j.velocity - p_i.velocity) * (p_j.mass / p_j.density * w_poly6(r_sq, h));
+				});
+				c * dv
+			})
+			.collect::<Vec<_>>();
+
+		for (p, dv) in self.list.iter_mut().zip(corrections) {
+			p.velocity += dv;
+		}
+	}
+}
@@ -0,0 +1,230 @@
+// Viscosity models, explicit forces and an implicit solve
+use super::{spiky_grad, w_visc, Particles};
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Viscosity {
+	// laplacian of the viscosity kernel, Müller et al. 2003, with dynamic viscosity mu
+	Laplacian {
+		dynamic: f32,
+	},
+	// Monaghan 1992, only between approaching particles, scaled by the speed of sound
+	// alpha is linear in the approach speed, beta quadratic and stops shocks
+	Artificial {
+		alpha: f32,
+		beta: f32,
+	},
+	// Morris et al. 1997, kinematic viscosity nu in length^2 / time
+	Morris {
+		kinematic: f32,
+	},
+	// the Morris model integrated implicitly with conjugate gradients after every step,
+	// stays stable for honey or lava at any time step
+	Implicit {
+		kinematic: f32,
+		// relative residual the solve stops at
+		tolerance: f32,
+		max_iterations: usize,
+	},
+}
+
+impl Default for Viscosity {
+	fn default() -> Self {
+		Viscosity::Laplacian { dynamic: 0.01 }
+	}
+}
+
+impl Viscosity {
+	// every model with starting coefficients, for switching between them
+	pub const ALL: [Viscosity; 4] = [
+		Viscosity::Laplacian { dynamic: 0.01 },
+		Viscosity::Artificial {
+			alpha: 0.1,
+			beta: 0.0,
+		},
+		Viscosity::Morris { kinematic: 10.0 },
+		Viscosity::Implicit {
+			kinematic: 1000.0,
+			tolerance: 1e-4,
+			max_iterations: 100,
+		},
+	];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			Viscosity::Laplacian { .. } => "laplacian",
+			Viscosity::Artificial { .. } => "artificial",
+			Viscosity::Morris { .. } => "morris",
+			Viscosity::Implicit { .. } => "implicit",
+		}
+	}
+
+	// closest dynamic viscosity of the laplacian model, artificial viscosity has none
+	pub fn dynamic(self, rest_density: f32) -> f32 {
+		match self {
+			Viscosity::Laplacian { dynamic } => dynamic,
+			Viscosity::Artificial { .. } => 0.0,
+			Viscosity::Morris { kinematic } | Viscosity::Implicit { kinematic, .. } => {
+				kinematic * rest_density
+			}
+		}
+	}
+}
+
+// -x_ij . grad W_ij / (r^2 + 0.01 h^2) with x_ij = x_i - x_j = -r_ij, never negative
+// the same for i and j, which keeps the implicit system symmetric
+fn morris_factor(r_ij: Vector3<f32>, r_sq: f32, h: f32) -> f32 {
+	r_ij.dot(spiky_grad(r_ij, r_sq, h)) / (r_sq + 0.01 * h * h)
+}
+
+impl Particles {
+	// kinematic viscosity the explicit time step limit has to respect, none for the implicit solve
+	pub(super) fn explicit_viscosity(&self) -> f32 {
+		match self.viscosity {
+			Viscosity::Laplacian { dynamic } => dynamic / self.rest_density,
+			// Monaghan's equivalent alpha c h / (2 (d + 2))
+			Viscosity::Artificial { alpha, .. } => {
+				alpha * self.eos.speed_of_sound() * self.h / 10.0
+			}
+			Viscosity::Morris { kinematic } => kinematic,
+			Viscosity::Implicit { .. } => 0.0,
+		}
+	}
+
+	// viscosity force per unit volume of every particle, zero for the implicit model
+	// only valid after `update_grid`
+	pub(super) fn viscosity_forces(&self) -> Vec<Vector3<f32>> {
+		let h = self.h;
+		let speed_of_sound = self.eos.speed_of_sound();
+		(0..self.list.len())
+			.map(|i| {
+				let p_i = self.list[i];
+				let mut f = Vector3::zero();
+				self.for_each_neighbor(i, |j, r_ij, r_sq| {
+					if i == j || r_sq == 0.0 {
+						return;
+					}
+					let p_j = self.list[j];
+					let v_ij = p_i.velocity - p_j.velocity;
+					f += match self.viscosity {
+						Viscosity::Laplacian { dynamic } => {
+							-v_ij * (dynamic * p_j.mass * w_visc(r_sq, h) / p_j.density)
+						}
+						Viscosity::Artificial { alpha, beta } => {
+							// x_ij . v_ij < 0 while approaching
+							let approach = (-r_ij).dot(v_ij);
+							if approach >= 0.0 {
+								return;
+							}
+							let mu = h * approach / (r_sq + 0.01 * h * h);
+							let density = 0.5 * (p_i.density + p_j.density);
+							let pi = (-alpha * speed_of_sound * mu + beta * mu * mu) / density;
+							-spiky_grad(r_ij, r_sq, h) * (p_i.density * p_j.mass * pi)
+						}
+						Viscosity::Morris { kinematic } => {
+							let mu = kinematic * (p_i.density + p_j.density) / p_j.density;
+							-v_ij * (mu * p_j.mass * morris_factor(r_ij, r_sq, h))
+						}
+						Viscosity::Implicit { .. } => Vector3::zero(),
+					};
+				});
+				f
+			})
+			.collect()
+	}
+
+	// solves (M + dt L) v = M v* for the new velocities, L the Morris operator weighted by mass,
+	// with Jacobi preconditioned conjugate gradients on all three components at once
+	pub(super) fn solve_viscosity(&mut self, dt: f32) -> usize {
+		let Viscosity::Implicit {
+			kinematic,
+			tolerance,
+			max_iterations,
+		} = self.viscosity
+		else {
+			return 0;
+		};
+		let n = self.list.len();
+		if n == 0 {
+			return 0;
+		}
+		self.update_grid();
+
+		// off diagonal couplings b_ij, the matrix is m_i + dt sum_j b_ij on the diagonal
+		// and -dt b_ij off it
+		let couplings = (0..n)
+			.map(|i| {
+				let p_i = self.list[i];
+				let mut row = Vec::new();
+				self.for_each_neighbor(i, |j, r_ij, r_sq| {
+					if i != j && r_sq > 0.0 {
+						let p_j = self.list[j];
+						let b = kinematic * p_i.mass * p_j.mass * (p_i.density + p_j.density)
+							/ (p_i.density * p_j.density)
+							* morris_factor(r_ij, r_sq, self.h);
+						row.push((j, dt * b));
+					}
+				});
+				row
+			})
+			.collect::<Vec<_>>();
+		let diagonal = (0..n)
+			.map(|i| self.list[i].mass + couplings[i].iter().map(|&(_, b)| b).sum::<f32>())
+			.collect::<Vec<_>>();
+		let apply = |x: &[Vector3<f32>]| {
+			(0..n)
+				.map(|i| {
+					couplings[i]
+						.iter()
+						.fold(x[i] * diagonal[i], |sum, &(j, b)| sum - x[j] * b)
+				})
+				.collect::<Vec<_>>()
+		};
+		let dot = |a: &[Vector3<f32>], b: &[Vector3<f32>]| {
+			a.iter().zip(b).map(|(a, b)| a.dot(*b) as f64).sum::<f64>()
+		};
+
+		let rhs = self
+			.list
+			.iter()
+			.map(|p| p.velocity * p.mass)
+			.collect::<Vec<_>>();
+		// the current velocities are a good first guess
+		let mut x = self.list.iter().map(|p| p.velocity).collect::<Vec<_>>();
+		let ax = apply(&x);
+		let mut r = rhs.iter().zip(ax).map(|(b, ax)| b - ax).collect::<Vec<_>>();
+		let mut z = r
+			.iter()
+			.zip(&diagonal)
+			.map(|(r, d)| r / *d)
+			.collect::<Vec<_>>();
+		let mut p = z.clone();
+		let mut rz = dot(&r, &z);
+		let stop = tolerance as f64 * dot(&rhs, &rhs).sqrt();
+
+		let mut iterations = 0;
+		while iterations < max_iterations && dot(&r, &r).sqrt() > stop {
+			let ap = apply(&p);
+			let alpha = (rz / dot(&p, &ap)) as f32;
+			for i in 0..n {
+				x[i] += p[i] * alpha;
+				r[i] -= ap[i] * alpha;
+				z[i] = r[i] / diagonal[i];
+			}
+			let rz_next = dot(&r, &z);
+			let beta = (rz_next / rz) as f32;
+			rz = rz_next;
+			for i in 0..n {
+				p[i] = z[i] + p[i] * beta;
+			}
+			iterations += 1;
+		}
+
+		for (p, v) in self.list.iter_mut().zip(x) {
+			p.velocity = v;
+		}
+		iterations
+	}
+}
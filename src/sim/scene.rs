@@ -0,0 +1,307 @@
+use super::collider::{Collider, Shape};
+use super::emitter::Emitter;
+use super::eos::EquationOfState;
+use super::export::ExportSettings;
+use super::force::{Field, ForceField};
+use super::particle::{
+	Attribute, DfsphParams, Integrator, PbfParams, Solver, SurfaceTension, VelocityParams,
+	Viscosity,
+};
+use super::sink::Sink;
+use super::surface::Surface;
+use super::timestep::TimeStep;
+use anyhow::Context;
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// Everything needed to set up a simulation, loaded from a RON file.
+// Missing fields fall back to the defaults below, which are the built-in scene.
+// See `scenes/` for examples.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Scene {
+	// limit on the particle count, fluids and emitters stop adding particles past it,
+	// the particle buffers grow and shrink with the count below that
+	pub max_particles: usize,
+	// random when missing
+	pub seed: Option<u64>,
+	pub material: Material,
+	pub fluids: Vec<Fluid>,
+	pub emitters: Vec<Emitter>,
+	// remove the particles reaching them
+	pub sinks: Vec<Sink>,
+	pub colliders: Vec<Collider>,
+	pub fields: Vec<ForceField>,
+	pub camera: CameraPose,
+	pub solver: SolverSettings,
+	// reconstruction settings for drawing the liquid surface
+	pub surface: Surface,
+	// write particle files while running, off when missing
+	pub export: Option<ExportSettings>,
+	pub visualization: Visualization,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Material {
+	pub rest_density: f32,
+	pub viscosity: Viscosity,
+	// initial particle spacing, particles get mass rest_density * spacing^3 and radius spacing / 2
+	pub spacing: f32,
+	// random per particle when missing
+	pub color: Option<[f32; 3]>,
+	pub surface_tension: SurfaceTension,
+	// acceleration pulling the fluid toward collider surfaces, makes it wet them
+	pub adhesion: f32,
+}
+
+// Initial fluid volumes, filled on a cubic lattice.
+#[derive(Debug, Copy, Clone, Deserialize)]
+pub enum Fluid {
+	Block {
+		min: Vector3<f32>,
+		max: Vector3<f32>,
+		#[serde(default = "zero")]
+		velocity: Vector3<f32>,
+	},
+	Sphere {
+		center: Vector3<f32>,
+		radius: f32,
+		#[serde(default = "zero")]
+		velocity: Vector3<f32>,
+	},
+	// normal distributed random cloud
+	Cloud {
+		center: Vector3<f32>,
+		deviation: Vector3<f32>,
+		count: usize,
+	},
+}
+
+// Angles in degrees.
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(default)]
+pub struct CameraPose {
+	pub target: Vector3<f32>,
+	pub yaw: f32,
+	pub pitch: f32,
+	pub distance: f32,
+	pub fovy: f32,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(default)]
+pub struct SolverSettings {
+	pub solver: Solver,
+	pub integrator: Integrator,
+	// smoothing length
+	pub h: f32,
+	pub eos: EquationOfState,
+	// what E swaps `eos` with, the other kind with its default parameters when missing
+	pub other_eos: Option<EquationOfState>,
+	pub timestep: TimeStep,
+	pub pbf: PbfParams,
+	pub dfsph: DfsphParams,
+	// vorticity confinement and XSPH smoothing, for every solver
+	// off when missing, except for PBF, see `VelocityParams::default_for`
+	pub velocity: Option<VelocityParams>,
+}
+
+// Coloring particles by a scalar attribute instead of their own color.
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Visualization {
+	// particle colors when missing
+	pub attribute: Option<Attribute>,
+	pub colormap: Colormap,
+	// (min, max) mapped to the ends of the colormap, follows the current values when missing
+	pub range: Option<(f32, f32)>,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Colormap {
+	#[default]
+	Viridis,
+	// diverging, for signed values like the density error
+	Coolwarm,
+	Turbo,
+}
+
+impl Colormap {
+	pub fn name(self) -> &'static str {
+		match self {
+			Colormap::Viridis => "viridis",
+			Colormap::Coolwarm => "coolwarm",
+			Colormap::Turbo => "turbo",
+		}
+	}
+}
+
+pub(crate) fn zero() -> Vector3<f32> {
+	Vector3::zero()
+}
+
+impl Scene {
+	pub fn load(path: &Path) -> anyhow::Result<Self> {
+		let text = std::fs::read_to_string(path)
+			.with_context(|| format!("could not read {}", path.display()))?;
+		ron::from_str(&text).with_context(|| format!("could not parse {}", path.display()))
+	}
+
+	// picks a random seed for scenes without one and keeps it,
+	// so every `Particles::new` from this scene starts from the same state
+	pub fn resolve_seed(&mut self) -> u64 {
+		*self.seed.get_or_insert_with(rand::random)
+	}
+}
+
+impl Default for Scene {
+	fn default() -> Self {
+		Self {
+			max_particles: 10_000,
+			seed: None,
+			material: Material::default(),
+			fluids: vec![Fluid::Cloud {
+				center: Vector3::zero(),
+				deviation: Vector3::new(20.0, 10.0, 10.0),
+				count: 1_000,
+			}],
+			emitters: Vec::new(),
+			sinks: Vec::new(),
+			colliders: vec![
+				Collider::new(Shape::Box {
+					min: Vector3::new(-150.0, -100.0, -100.0),
+					max: Vector3::new(150.0, 100.0, 100.0),
+				}),
+				Collider::new(Shape::Plane {
+					normal: Vector3::unit_y(),
+					offset: -60.0,
+				}),
+				Collider::new(Shape::Sphere {
+					center: Vector3::new(0.0, -60.0, 0.0),
+					radius: 30.0,
+				}),
+			],
+			fields: vec![
+				ForceField::new(Field::Point {
+					center: Vector3::zero(),
+					strength: 0.000001,
+				}),
+				ForceField::new(Field::Drag {
+					coefficient: 0.000001,
+				}),
+				ForceField::new(Field::Noise { amplitude: 0.00001 }),
+				ForceField::disabled(Field::Gravity {
+					acceleration: Vector3::new(0.0, -0.05, 0.0),
+				}),
+				ForceField::disabled(Field::Vortex {
+					center: Vector3::zero(),
+					axis: Vector3::unit_y(),
+					strength: 0.000002,
+				}),
+			],
+			camera: CameraPose::default(),
+			solver: SolverSettings::default(),
+			surface: Surface::default(),
+			export: None,
+			visualization: Visualization::default(),
+		}
+	}
+}
+
+impl Default for Material {
+	fn default() -> Self {
+		Self {
+			rest_density: 0.001,
+			viscosity: Viscosity::default(),
+			spacing: 10.0,
+			color: None,
+			surface_tension: SurfaceTension::None,
+			adhesion: 0.0,
+		}
+	}
+}
+
+impl Default for CameraPose {
+	fn default() -> Self {
+		Self {
+			target: Vector3::zero(),
+			yaw: 0.0,
+			pitch: 0.0,
+			distance: 300.0,
+			fovy: 45.0,
+		}
+	}
+}
+
+impl Default for SolverSettings {
+	fn default() -> Self {
+		Self {
+			solver: Solver::StateEquation,
+			integrator: Integrator::SymplecticEuler,
+			h: 16.0,
+			eos: EquationOfState::default(),
+			other_eos: None,
+			timestep: TimeStep::new(0.5),
+			pbf: PbfParams::default(),
+			dfsph: DfsphParams::default(),
+			velocity: None,
+		}
+	}
+}
+
+impl Fluid {
+	// lattice points (or samples) of the volume, with their initial velocity
+	pub fn positions(
+		&self,
+		spacing: f32,
+		rng: &mut impl rand::Rng,
+	) -> Vec<(Vector3<f32>, Vector3<f32>)> {
+		match *self {
+			Fluid::Block { min, max, velocity } => {
+				lattice(min, max, spacing).map(|x| (x, velocity)).collect()
+			}
+			Fluid::Sphere {
+				center,
+				radius,
+				velocity,
+			} => {
+				let extent = Vector3::new(radius, radius, radius);
+				lattice(center - extent, center + extent, spacing)
+					.filter(|x| (x - center).magnitude2() <= radius * radius)
+					.map(|x| (x, velocity))
+					.collect()
+			}
+			Fluid::Cloud {
+				center,
+				deviation,
+				count,
+			} => (0..count)
+				.map(|_| {
+					let x = rng.sample::<f32, _>(rand_distr::StandardNormal) * deviation.x;
+					let y = rng.sample::<f32, _>(rand_distr::StandardNormal) * deviation.y;
+					let z = rng.sample::<f32, _>(rand_distr::StandardNormal) * deviation.z;
+					(center + Vector3 { x, y, z }, Vector3::zero())
+				})
+				.collect(),
+		}
+	}
+}
+
+// cell centers of a cubic lattice inside the box
+pub(crate) fn lattice(
+	min: Vector3<f32>,
+	max: Vector3<f32>,
+	spacing: f32,
+) -> impl Iterator<Item = Vector3<f32>> {
+	let count = |axis: usize| ((max[axis] - min[axis]) / spacing).floor().max(0.0) as usize;
+	let (nx, ny, nz) = (count(0), count(1), count(2));
+	let offset = min + Vector3::new(0.5, 0.5, 0.5) * spacing;
+	(0..nx).flat_map(move |i| {
+		(0..ny).flat_map(move |j| {
+			(0..nz).map(move |k| offset + Vector3::new(i as f32, j as f32, k as f32) * spacing)
+		})
+	})
+}
@@ -0,0 +1,59 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+
+// Where a sink removes particles.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum Region {
+	// behind an infinite plane, the normal points toward the side that is kept
+	Plane {
+		normal: Vector3<f32>,
+		offset: f32,
+	},
+	// inside the box, like an outlet or a drain
+	Box {
+		min: Vector3<f32>,
+		max: Vector3<f32>,
+	},
+	// farther than `radius` from `center`, catches particles escaping the scene
+	Distance {
+		center: Vector3<f32>,
+		radius: f32,
+	},
+}
+
+// Removes the particles that reach its region, for outflow boundaries.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Sink {
+	pub region: Region,
+	#[serde(default = "enabled")]
+	pub enabled: bool,
+}
+
+fn enabled() -> bool {
+	true
+}
+
+impl Sink {
+	pub fn new(region: Region) -> Self {
+		Self {
+			region,
+			enabled: true,
+		}
+	}
+
+	pub fn contains(&self, position: Vector3<f32>) -> bool {
+		if !self.enabled {
+			return false;
+		}
+		match self.region {
+			Region::Plane { normal, offset } => position.dot(normal.normalize()) < offset,
+			Region::Box { min, max } => {
+				(0..3).all(|i| min[i] <= position[i] && position[i] <= max[i])
+			}
+			Region::Distance { center, radius } => {
+				(position - center).magnitude2() > radius * radius
+			}
+		}
+	}
+}
@@ -0,0 +1,337 @@
+use super::Particle;
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::OnceLock;
+
+// Liquid surface reconstruction after Zhu and Bridson, "Animating sand as a fluid" (2005).
+// Particles are splatted onto a grid as phi(x) = |x - x_avg| - r_avg, with x_avg and r_avg the
+// kernel weighted averages of nearby particle positions and radii.
+// The zero level set is extracted with marching cubes.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Surface {
+	// grid cell size, in particle radii
+	pub cell_size: f32,
+	// splatting kernel radius, in particle radii
+	pub kernel_radius: f32,
+	// cells along the longest axis are capped, stray particles would blow up the grid otherwise
+	pub max_cells: usize,
+}
+
+impl Default for Surface {
+	fn default() -> Self {
+		Self {
+			cell_size: 1.0,
+			kernel_radius: 4.0,
+			max_cells: 160,
+		}
+	}
+}
+
+// Indexed triangles, counter-clockwise seen from outside the liquid.
+#[derive(Debug, Clone, Default)]
+pub struct TriangleMesh {
+	pub positions: Vec<Vector3<f32>>,
+	pub normals: Vec<Vector3<f32>>,
+	pub indices: Vec<u32>,
+}
+
+impl Surface {
+	pub fn reconstruct(&self, particles: &[Particle]) -> TriangleMesh {
+		if particles.is_empty() {
+			return TriangleMesh::default();
+		}
+		let field = self.splat(particles);
+		field.march()
+	}
+
+	fn splat(&self, particles: &[Particle]) -> Field {
+		let radius = particles.iter().map(|p| p.radius).fold(0.0, f32::max);
+		let support = self.kernel_radius * radius;
+
+		let mut min = particles[0].position;
+		let mut max = particles[0].position;
+		for p in particles {
+			for a in 0..3 {
+				min[a] = min[a].min(p.position[a]);
+				max[a] = max[a].max(p.position[a]);
+			}
+		}
+		// keep the outermost nodes outside so the surface is closed
+		let pad = Vector3::new(1.0, 1.0, 1.0) * (support + radius);
+		let (min, max) = (min - pad, max + pad);
+		let extent = max - min;
+		let longest = extent.x.max(extent.y).max(extent.z);
+		let cell = (self.cell_size * radius).max(longest / self.max_cells.max(1) as f32);
+		let dims = [0, 1, 2].map(|a| (extent[a] / cell).ceil() as usize + 1);
+
+		let n = dims[0] * dims[1] * dims[2];
+		let mut weight = vec![0.0f32; n];
+		let mut center = vec![Vector3::zero(); n];
+		let mut radii = vec![0.0f32; n];
+
+		let node = |i: [usize; 3]| min + Vector3::new(i[0] as f32, i[1] as f32, i[2] as f32) * cell;
+		let index = |i: [usize; 3]| (i[2] * dims[1] + i[1]) * dims[0] + i[0];
+
+		for p in particles {
+			let lo = [0, 1, 2]
+				.map(|a| ((p.position[a] - support - min[a]) / cell).floor().max(0.0) as usize);
+			let hi = [0, 1, 2].map(|a| {
+				(((p.position[a] + support - min[a]) / cell).ceil() as usize).min(dims[a] - 1)
+			});
+			for k in lo[2]..=hi[2] {
+				for j in lo[1]..=hi[1] {
+					for i in lo[0]..=hi[0] {
+						let s = (node([i, j, k]) - p.position).magnitude2() / (support * support);
+						if s < 1.0 {
+							let w = (1.0 - s) * (1.0 - s) * (1.0 - s);
+							let idx = index([i, j, k]);
+							weight[idx] += w;
+							center[idx] += p.position * w;
+							radii[idx] += p.radius * w;
+						}
+					}
+				}
+			}
+		}
+
+		let mut phi = vec![support; n];
+		for k in 0..dims[2] {
+			for j in 0..dims[1] {
+				for i in 0..dims[0] {
+					let idx = index([i, j, k]);
+					let w = weight[idx];
+					if w > 0.0 {
+						phi[idx] = (node([i, j, k]) - center[idx] / w).magnitude() - radii[idx] / w;
+					}
+				}
+			}
+		}
+
+		Field {
+			min,
+			cell,
+			dims,
+			phi,
+		}
+	}
+}
+
+// signed distance like values on the grid nodes, negative inside
+struct Field {
+	min: Vector3<f32>,
+	cell: f32,
+	dims: [usize; 3],
+	phi: Vec<f32>,
+}
+
+impl Field {
+	fn index(&self, i: [usize; 3]) -> usize {
+		(i[2] * self.dims[1] + i[1]) * self.dims[0] + i[0]
+	}
+
+	fn march(&self) -> TriangleMesh {
+		let cases = cases();
+		let mut mesh = TriangleMesh::default();
+		// vertices are shared between cells, keyed by the grid edge they sit on
+		let mut vertices = HashMap::new();
+
+		for k in 0..self.dims[2] - 1 {
+			for j in 0..self.dims[1] - 1 {
+				for i in 0..self.dims[0] - 1 {
+					let corner = |c: usize| [i + (c & 1), j + ((c >> 1) & 1), k + ((c >> 2) & 1)];
+					let values = [0, 1, 2, 3, 4, 5, 6, 7].map(|c| self.phi[self.index(corner(c))]);
+					let case = (0..8).fold(0, |case, c| case | (usize::from(values[c] < 0.0) << c));
+
+					for patch in &cases[case] {
+						let outline = patch
+							.outline
+							.iter()
+							.map(|&edge| {
+								let (a, b) = EDGES[edge as usize];
+								let key =
+									self.index(corner(a)) * 3 + (a ^ b).trailing_zeros() as usize;
+								*vertices.entry(key).or_insert_with(|| {
+									let t = values[a] / (values[a] - values[b]);
+									let pa = self.position(corner(a));
+									let pb = self.position(corner(b));
+									mesh.positions.push(pa + (pb - pa) * t.clamp(0.0, 1.0));
+									(mesh.positions.len() - 1) as u32
+								})
+							})
+							.collect::<Vec<_>>();
+
+						// the outline runs clockwise seen from outside the liquid
+						let n = outline.len();
+						if patch.center {
+							let center = outline
+								.iter()
+								.map(|&v| mesh.positions[v as usize])
+								.sum::<Vector3<f32>>() / n as f32;
+							mesh.positions.push(center);
+							let c = (mesh.positions.len() - 1) as u32;
+							for i in 0..n {
+								mesh.indices.extend([c, outline[(i + 1) % n], outline[i]]);
+							}
+						} else {
+							for i in 1..n - 1 {
+								mesh.indices
+									.extend([outline[0], outline[i + 1], outline[i]]);
+							}
+						}
+					}
+				}
+			}
+		}
+
+		// area weighted face normals
+		mesh.normals = vec![Vector3::zero(); mesh.positions.len()];
+		for t in mesh.indices.chunks_exact(3) {
+			let [a, b, c] = [t[0], t[1], t[2]].map(|i| mesh.positions[i as usize]);
+			let n = (b - a).cross(c - a);
+			for &i in t {
+				mesh.normals[i as usize] += n;
+			}
+		}
+		for n in &mut mesh.normals {
+			*n = if n.magnitude2() > 0.0 {
+				n.normalize()
+			} else {
+				Vector3::unit_y()
+			};
+		}
+		mesh
+	}
+
+	fn position(&self, i: [usize; 3]) -> Vector3<f32> {
+		self.min + Vector3::new(i[0] as f32, i[1] as f32, i[2] as f32) * self.cell
+	}
+}
+
+// Corners are numbered x + 2y + 4z, edges are the corner pairs below.
+const EDGES: [(usize, usize); 12] = [
+	(0, 1),
+	(2, 3),
+	(4, 5),
+	(6, 7),
+	(0, 2),
+	(1, 3),
+	(4, 6),
+	(5, 7),
+	(0, 4),
+	(1, 5),
+	(2, 6),
+	(3, 7),
+];
+
+fn edge(a: usize, b: usize) -> u8 {
+	let pair = (a.min(b), a.max(b));
+	EDGES.iter().position(|&e| e == pair).unwrap() as u8
+}
+
+// Piece of the surface inside one cube, outlined by the cube edges it crosses.
+struct Patch {
+	outline: Vec<u8>,
+	// fan around an extra vertex in the middle, when every fan from a corner of the outline
+	// would put a diagonal on a cube face and clash with the neighbouring cube
+	center: bool,
+}
+
+// Patches for all 256 inside/outside corner patterns.
+// Instead of the usual hand written table, the surface outline on every cube face is traced and
+// the closed loops become patches. Ambiguous faces always separate the inside corners,
+// so neighbouring cubes agree on the shared face and the mesh has no cracks.
+// Traced once and kept for every later reconstruction.
+fn cases() -> &'static [Vec<Patch>] {
+	static CASES: OnceLock<Vec<Vec<Patch>>> = OnceLock::new();
+	CASES.get_or_init(trace_cases)
+}
+
+fn trace_cases() -> Vec<Vec<Patch>> {
+	(0..256)
+		.map(|case: usize| {
+			let inside = |c: usize| case >> c & 1 == 1;
+			// for every edge where the outline leaves the inside, the edge where it comes back
+			let mut next = HashMap::new();
+			for axis in 0..3 {
+				let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+				for side in 0..2 {
+					// corners in counter-clockwise order seen from outside the cube
+					let mut cycle = [(0, 0), (1, 0), (1, 1), (0, 1)]
+						.map(|(a, b)| side << axis | a << u | b << v);
+					if side == 0 {
+						cycle.reverse();
+					}
+					let edges = [0, 1, 2, 3].map(|k| (cycle[k], cycle[(k + 1) % 4]));
+					for k in 0..4 {
+						let (a, b) = edges[k];
+						if inside(a) && !inside(b) {
+							// walk back to the closest edge entering the inside
+							let back = (1..4)
+								.map(|d| edges[(k + 4 - d) % 4])
+								.find(|&(a, b)| !inside(a) && inside(b))
+								.unwrap();
+							next.insert(edge(a, b), edge(back.0, back.1));
+						}
+					}
+				}
+			}
+
+			let mut patches = Vec::new();
+			while let Some(&start) = next.keys().min() {
+				let mut outline = vec![start];
+				let mut e = next.remove(&start).unwrap();
+				while e != start {
+					outline.push(e);
+					e = next.remove(&e).unwrap();
+				}
+
+				let n = outline.len();
+				let fan = (0..n)
+					.find(|&r| (2..n - 1).all(|i| !same_face(outline[r], outline[(r + i) % n])));
+				match fan {
+					Some(r) => {
+						outline.rotate_left(r);
+						patches.push(Patch {
+							outline,
+							center: false,
+						});
+					}
+					None => patches.push(Patch {
+						outline,
+						center: true,
+					}),
+				}
+			}
+			patches
+		})
+		.collect()
+}
+
+fn same_face(e: u8, f: u8) -> bool {
+	let (a, b) = EDGES[e as usize];
+	let (c, d) = EDGES[f as usize];
+	// some coordinate is 0 or 1 for all four corners
+	(a & b & c & d) | (!(a | b | c | d) & 7) != 0
+}
+
+impl TriangleMesh {
+	pub fn write_obj(&self, writer: impl Write) -> std::io::Result<()> {
+		let mut w = std::io::BufWriter::new(writer);
+		writeln!(w, "# wgpu_fluid surface")?;
+		for p in &self.positions {
+			writeln!(w, "v {} {} {}", p.x, p.y, p.z)?;
+		}
+		for n in &self.normals {
+			writeln!(w, "vn {} {} {}", n.x, n.y, n.z)?;
+		}
+		for t in self.indices.chunks_exact(3) {
+			let [a, b, c] = [t[0] + 1, t[1] + 1, t[2] + 1];
+			writeln!(w, "f {a}//{a} {b}//{b} {c}//{c}")?;
+		}
+		w.flush()
+	}
+}
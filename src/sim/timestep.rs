@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+// Picks the substep size from the usual SPH stability limits.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeStep {
+	// simulated time per call to `Particles::update`, one viewer clock tick or headless frame
+	pub frame_time: f32,
+	pub adaptive: bool,
+	pub min_dt: f32,
+	pub max_dt: f32,
+	// dt <= cfl * h / v_max
+	pub cfl: f32,
+	// dt <= force * sqrt(h / a_max)
+	pub force: f32,
+	// dt <= viscous * h^2 / nu
+	pub viscous: f32,
+	// substeps per frame, simulated time is dropped when this runs out
+	pub max_substeps: usize,
+}
+
+impl Default for TimeStep {
+	fn default() -> Self {
+		Self::new(0.5)
+	}
+}
+
+impl TimeStep {
+	pub fn new(frame_time: f32) -> Self {
+		Self {
+			frame_time,
+			adaptive: true,
+			min_dt: frame_time / 50.0,
+			max_dt: frame_time,
+			cfl: 0.4,
+			force: 0.25,
+			viscous: 0.125,
+			max_substeps: 8,
+		}
+	}
+
+	pub fn stable_dt(
+		&self,
+		h: f32,
+		max_velocity: f32,
+		max_acceleration: f32,
+		viscosity: f32,
+	) -> f32 {
+		if !self.adaptive {
+			return self.frame_time;
+		}
+
+		let mut dt = self.max_dt;
+		if max_velocity > 0.0 {
+			dt = dt.min(self.cfl * h / max_velocity);
+		}
+		if max_acceleration > 0.0 {
+			dt = dt.min(self.force * (h / max_acceleration).sqrt());
+		}
+		if viscosity > 0.0 {
+			dt = dt.min(self.viscous * h * h / viscosity);
+		}
+		dt.max(self.min_dt)
+	}
+
+	// splits what is left of the frame into equal steps no larger than `dt`
+	pub fn substep(&self, remaining: f32, dt: f32) -> f32 {
+		let steps = (remaining / dt).ceil().max(1.0);
+		remaining / steps
+	}
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use wgpu_fluid::sim::{Attribute, Particles};
+
+// lattice of spacing 10 with h 16, see the scene defaults
+fn block() -> Particles {
+	let mut particles = Particles::new(&common::cube(50.0));
+	particles.update_grid();
+	particles.update_pressure();
+	particles
+}
+
+// far enough from the sides of the block to have a full neighborhood
+fn interior(particles: &Particles) -> Vec<usize> {
+	(0..particles.particles().len())
+		.filter(|&i| {
+			let x = particles.particles()[i].position;
+			x.x.abs().max(x.y.abs()).max(x.z.abs()) < 20.0
+		})
+		.collect()
+}
+
+#[test]
+fn one_value_per_particle() {
+	let mut particles = block();
+	for attribute in Attribute::ALL {
+		let values = particles.attribute(attribute);
+		assert_eq!(
+			values.len(),
+			particles.particles().len(),
+			"{}",
+			attribute.name()
+		);
+		assert!(values.iter().all(|v| v.is_finite()), "{}", attribute.name());
+	}
+}
+
+#[test]
+fn speed_and_density() {
+	let mut particles = block();
+	for (i, p) in particles.particles_mut().iter_mut().enumerate() {
+		p.velocity = Vector3::new(i as f32, 0.0, 0.0);
+	}
+	let speed = particles.attribute(Attribute::Speed);
+	assert!(speed.iter().enumerate().all(|(i, &s)| s == i as f32));
+
+	let density = particles.attribute(Attribute::Density);
+	let error = particles.attribute(Attribute::DensityError);
+	let rest_density = particles.rest_density();
+	for (d, e) in density.iter().zip(error) {
+		assert!((d / rest_density - 1.0 - e).abs() < 1e-5);
+	}
+}
+
+#[test]
+fn neighbor_count() {
+	let mut particles = block();
+	let neighbors = particles.attribute(Attribute::Neighbors);
+	// itself, 6 faces and 12 edges of the lattice are closer than h, the 8 corners aren't
+	for i in interior(&particles) {
+		assert_eq!(neighbors[i], 19.0);
+	}
+	assert!(neighbors.iter().all(|&n| (1.0..=19.0).contains(&n)));
+}
+
+#[test]
+fn vorticity_of_rigid_rotation() {
+	let mut particles = block();
+	// the curl of omega x r is 2 omega
+	let omega = Vector3::new(0.0, 0.01, 0.0);
+	for p in particles.particles_mut() {
+		p.velocity = omega.cross(p.position);
+	}
+	let vorticity = particles.attribute(Attribute::Vorticity);
+	for i in interior(&particles) {
+		let expected = 2.0 * omega.magnitude();
+		assert!(
+			(vorticity[i] / expected - 1.0).abs() < 0.25,
+			"vorticity {}, expected {expected}",
+			vorticity[i]
+		);
+	}
+
+	// and a uniform flow has none
+	for p in particles.particles_mut() {
+		p.velocity = Vector3::new(1.0, 2.0, 3.0);
+	}
+	let vorticity = particles.attribute(Attribute::Vorticity);
+	assert!(vorticity.iter().all(|&w| w < 1e-5));
+}
@@ -0,0 +1,75 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use wgpu_fluid::sim::scene::Fluid;
+use wgpu_fluid::sim::{Particles, Scene, Solver};
+
+fn assert_same(a: &Particles, b: &Particles) {
+	assert_eq!(a.time(), b.time());
+	assert_eq!(a.particles().len(), b.particles().len());
+	for (p, q) in a.particles().iter().zip(b.particles()) {
+		assert_eq!(p.position, q.position);
+		assert_eq!(p.velocity, q.velocity);
+		assert_eq!(p.density, q.density);
+		assert_eq!(p.pressure, q.pressure);
+		assert_eq!(p.color, q.color);
+	}
+}
+
+#[test]
+fn resumed_run_matches_uninterrupted_run() {
+	let path = std::env::temp_dir().join(format!("wgpu_fluid_checkpoint_{}", std::process::id()));
+	for solver in [Solver::StateEquation, Solver::Pbf, Solver::Dfsph] {
+		// the default fields include noise, so the random draws matter too
+		let mut scene = Scene {
+			seed: Some(3),
+			fluids: vec![Fluid::Block {
+				min: Vector3::new(-50.0, -50.0, -50.0),
+				max: Vector3::new(50.0, 50.0, 50.0),
+				velocity: Vector3::zero(),
+			}],
+			..Scene::default()
+		};
+		scene.solver.solver = solver;
+		let mut original = Particles::new(&scene);
+		for _ in 0..5 {
+			original.update();
+		}
+
+		original.save_file(&path).unwrap();
+		let mut resumed = Particles::load_file(&path).unwrap();
+		assert_same(&original, &resumed);
+
+		for _ in 0..5 {
+			original.update();
+			resumed.update();
+		}
+		assert_same(&original, &resumed);
+
+		// new particles draw their colors from the generator
+		original.spawn(Vector3::zero(), Vector3::zero());
+		resumed.spawn(Vector3::zero(), Vector3::zero());
+		assert_same(&original, &resumed);
+	}
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn other_files_are_rejected() {
+	let mut bytes = Vec::new();
+	Particles::default().save(&mut bytes).unwrap();
+	assert!(Particles::load(bytes.as_slice()).is_ok());
+
+	let mut magic = bytes.clone();
+	magic[0] = b'X';
+	let e = Particles::load(magic.as_slice()).err().unwrap();
+	assert!(e.to_string().contains("not a checkpoint"), "{e}");
+
+	// the version follows the four magic bytes
+	let mut version = bytes.clone();
+	version[4..8].copy_from_slice(&999u32.to_le_bytes());
+	let e = Particles::load(version.as_slice()).err().unwrap();
+	assert!(e.to_string().contains("version 999"), "{e}");
+
+	assert!(Particles::load(&bytes[..bytes.len() / 2]).is_err());
+	assert!(Particles::load(&bytes[..2]).is_err());
+}
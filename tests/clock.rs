@@ -0,0 +1,75 @@
+use std::time::Duration;
+use wgpu_fluid::sim::clock::Clock;
+use wgpu_fluid::sim::{Particles, Scene};
+
+const TICK: Duration = Duration::from_millis(10);
+
+#[test]
+fn steps_follow_wall_clock_time() {
+	let mut clock = Clock::new(TICK);
+	// the same time in few long frames or many short ones gives the same steps
+	let long = (0..4)
+		.map(|_| clock.advance(Duration::from_millis(25)))
+		.sum::<usize>();
+	let mut clock = Clock::new(TICK);
+	let short = (0..20)
+		.map(|_| clock.advance(Duration::from_millis(5)))
+		.sum::<usize>();
+	assert_eq!(long, 10);
+	assert_eq!(short, 10);
+}
+
+#[test]
+fn time_scale() {
+	let mut clock = Clock::new(TICK);
+	clock.set_time_scale(0.5);
+	assert_eq!(clock.advance(Duration::from_millis(40)), 2);
+	clock.set_time_scale(2.0);
+	assert_eq!(clock.advance(Duration::from_millis(40)), 8);
+
+	clock.slower();
+	assert_eq!(clock.time_scale, 1.0);
+	for _ in 0..10 {
+		clock.faster();
+	}
+	assert_eq!(clock.time_scale, 8.0);
+}
+
+#[test]
+fn pause_and_single_step() {
+	let mut clock = Clock::new(TICK);
+	clock.toggle_pause();
+	assert_eq!(clock.advance(Duration::from_secs(1)), 0);
+	clock.step();
+	assert_eq!(clock.advance(Duration::from_secs(1)), 1);
+	assert_eq!(clock.advance(Duration::from_secs(1)), 0);
+
+	// no catching up on the time spent paused
+	clock.toggle_pause();
+	assert_eq!(clock.advance(Duration::from_millis(10)), 1);
+}
+
+#[test]
+fn long_frames_drop_time() {
+	let mut clock = Clock::new(TICK);
+	assert_eq!(clock.advance(Duration::from_secs(1)), clock.max_steps);
+	// and don't carry the backlog into the next frame
+	assert_eq!(clock.advance(Duration::ZERO), 0);
+}
+
+#[test]
+fn reset_scene_starts_from_the_same_particles() {
+	// the built-in scene has no seed, its cloud and colors are random
+	let mut scene = Scene::default();
+	assert_eq!(scene.seed, None);
+	let seed = scene.resolve_seed();
+	assert_eq!(scene.resolve_seed(), seed);
+
+	let first = Particles::new(&scene);
+	let reset = Particles::new(&scene);
+	assert_eq!(first.particles().len(), reset.particles().len());
+	for (p, q) in first.particles().iter().zip(reset.particles()) {
+		assert_eq!(p.position, q.position);
+		assert_eq!(p.color, q.color);
+	}
+}
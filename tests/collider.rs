@@ -0,0 +1,104 @@
+mod common;
+
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use wgpu_fluid::sim::collider::{Collider, Shape};
+use wgpu_fluid::sim::Particles;
+
+const FLOOR: Shape = Shape::Plane {
+	normal: Vector3::new(0.0, 1.0, 0.0),
+	offset: 0.0,
+};
+
+// a single particle at (5, 55, 5) moving down onto the floor
+fn falling(restitution: f32, friction: f32) -> Particles {
+	let mut scene = common::block(Vector3::new(0.0, 50.0, 0.0), Vector3::new(10.0, 60.0, 10.0));
+	scene.colliders = vec![Collider {
+		shape: FLOOR,
+		restitution,
+		friction,
+	}];
+	let mut particles = Particles::new(&scene);
+	assert_eq!(particles.particles().len(), 1);
+	particles.particles_mut()[0].velocity = Vector3::new(1.0, -2.0, 0.0);
+	particles
+}
+
+// velocity once it moves away from the floor, or still touches it after 100 frames
+fn bounce(mut particles: Particles) -> Vector3<f32> {
+	for _ in 0..100 {
+		particles.update();
+		let p = particles.particles()[0];
+		assert!(p.position.y >= p.radius - 1e-4, "{:?}", p.position);
+		if p.velocity.y >= 0.0 {
+			return p.velocity;
+		}
+	}
+	panic!("never reached the floor");
+}
+
+#[test]
+fn particle_bounces_with_the_restitution() {
+	for restitution in [0.0, 0.3, 0.8, 1.0] {
+		let v = bounce(falling(restitution, 0.0));
+		assert!(
+			(v.y - 2.0 * restitution).abs() < 1e-4,
+			"{restitution}: {v:?}"
+		);
+		// no friction, the tangential velocity is kept
+		assert!((v.x - 1.0).abs() < 1e-4, "{v:?}");
+	}
+}
+
+#[test]
+fn friction_slows_the_tangential_velocity() {
+	// the normal impulse is (1 + 0.5) 2 = 3, so 0.1 of it takes 0.3 off
+	let v = bounce(falling(0.5, 0.1));
+	assert!((v.x - 0.7).abs() < 1e-4, "{v:?}");
+	assert!((v.y - 1.0).abs() < 1e-4, "{v:?}");
+
+	// but never reverses it
+	let v = bounce(falling(0.5, 1.0));
+	assert_eq!(v.x, 0.0);
+}
+
+#[test]
+fn shapes_push_particles_to_their_side() {
+	let resolve = |shape: Shape, position: Vector3<f32>| {
+		let collider = Collider::new(shape);
+		let (mut position, mut velocity) = (position, Vector3::zero());
+		collider.resolve(&mut position, &mut velocity, 1.0);
+		let (distance, _) = collider.distance(position);
+		(position, distance)
+	};
+
+	// inside the box, at least a radius from every wall
+	let (x, distance) = resolve(
+		Shape::Box {
+			min: Vector3::new(-10.0, -10.0, -10.0),
+			max: Vector3::new(10.0, 10.0, 10.0),
+		},
+		Vector3::new(12.0, -15.0, 0.0),
+	);
+	assert_eq!(x, Vector3::new(9.0, -9.0, 0.0));
+	assert!((distance - 1.0).abs() < 1e-5);
+
+	// above the plane
+	let (x, distance) = resolve(FLOOR, Vector3::new(3.0, -5.0, 0.0));
+	assert_eq!(x, Vector3::new(3.0, 1.0, 0.0));
+	assert!((distance - 1.0).abs() < 1e-5);
+
+	// outside the sphere, along the line from its center
+	let (x, distance) = resolve(
+		Shape::Sphere {
+			center: Vector3::zero(),
+			radius: 10.0,
+		},
+		Vector3::new(0.0, 0.0, 5.0),
+	);
+	assert!(
+		(x - Vector3::new(0.0, 0.0, 11.0)).magnitude() < 1e-5,
+		"{x:?}"
+	);
+	assert!((distance - 1.0).abs() < 1e-5);
+}
@@ -0,0 +1,46 @@
+// Fixtures shared by the integration tests, each test uses some of them.
+#![allow(dead_code)]
+
+use cgmath::Vector3;
+use wgpu_fluid::sim::scene::Fluid;
+use wgpu_fluid::sim::{Particles, Scene};
+
+// a block of fluid at rest and nothing else, no colliders or force fields
+pub fn block(min: Vector3<f32>, max: Vector3<f32>) -> Scene {
+	Scene {
+		seed: Some(1),
+		fluids: vec![Fluid::Block {
+			min,
+			max,
+			velocity: Vector3::new(0.0, 0.0, 0.0),
+		}],
+		colliders: Vec::new(),
+		fields: Vec::new(),
+		..Scene::default()
+	}
+}
+
+// a block reaching `half` from the origin along every axis, a lattice of spacing 10 with the
+// default material
+pub fn cube(half: f32) -> Scene {
+	block(
+		Vector3::new(-half, -half, -half),
+		Vector3::new(half, half, half),
+	)
+}
+
+// +1 or -1 in a checkerboard on the lattice, so neighbors get opposite signs
+pub fn checkerboard(particles: &Particles) -> Vec<f32> {
+	particles
+		.particles()
+		.iter()
+		.map(|p| {
+			let cell = (p.position / 10.0).map(|x| x.floor() as i32);
+			if (cell.x + cell.y + cell.z).rem_euclid(2) == 0 {
+				1.0
+			} else {
+				-1.0
+			}
+		})
+		.collect()
+}
@@ -0,0 +1,65 @@
+use wgpu_fluid::sim::eos::EquationOfState;
+use wgpu_fluid::sim::{Particles, Scene};
+
+const REST_DENSITY: f32 = 0.001;
+const TAIT: EquationOfState = EquationOfState::Tait {
+	speed_of_sound: 10.0,
+	gamma: 7.0,
+};
+
+#[test]
+fn tait_clamps_negative_pressure() {
+	assert_eq!(TAIT.pressure(REST_DENSITY, REST_DENSITY), 0.0);
+	assert_eq!(TAIT.pressure(0.5 * REST_DENSITY, REST_DENSITY), 0.0);
+	assert!(TAIT.pressure(1.01 * REST_DENSITY, REST_DENSITY) > 0.0);
+
+	// the ideal gas pulls back below rest density
+	let gas = EquationOfState::IdealGas { gas_const: 50.0 };
+	assert!(gas.pressure(0.5 * REST_DENSITY, REST_DENSITY) < 0.0);
+}
+
+#[test]
+fn equations_of_state_differ_at_the_same_density() {
+	let gas = EquationOfState::IdealGas { gas_const: 50.0 };
+	let density = 1.01 * REST_DENSITY;
+	let (p_gas, p_tait) = (
+		gas.pressure(density, REST_DENSITY),
+		TAIT.pressure(density, REST_DENSITY),
+	);
+	assert!(p_gas > 0.0 && p_tait > 0.0);
+	assert!(
+		(p_gas - p_tait).abs() > 0.1 * p_gas.max(p_tait),
+		"{p_gas} {p_tait}"
+	);
+
+	// near rest density the slope is the speed of sound squared
+	let slope = TAIT.pressure(1.0001 * REST_DENSITY, REST_DENSITY) / (0.0001 * REST_DENSITY);
+	assert!((slope / 100.0 - 1.0).abs() < 0.01, "{slope}");
+	assert_eq!(TAIT.speed_of_sound(), 10.0);
+}
+
+#[test]
+fn toggle_swaps_in_the_scene_equation_of_state() {
+	let mut scene = Scene::default();
+	scene.solver.other_eos = Some(EquationOfState::Tait {
+		speed_of_sound: 20.0,
+		gamma: 3.0,
+	});
+	let mut particles = Particles::new(&scene);
+	particles.toggle_eos();
+	assert!(matches!(
+		particles.eos(),
+		EquationOfState::Tait { speed_of_sound, gamma } if speed_of_sound == 20.0 && gamma == 3.0
+	));
+	particles.toggle_eos();
+	assert!(matches!(
+		particles.eos(),
+		EquationOfState::IdealGas { gas_const } if gas_const == 50.0
+	));
+
+	// without one, the other kind with its defaults
+	scene.solver.other_eos = None;
+	let mut particles = Particles::new(&scene);
+	particles.toggle_eos();
+	assert!(matches!(particles.eos(), EquationOfState::Tait { .. }));
+}
@@ -0,0 +1,117 @@
+use cgmath::Vector3;
+use wgpu_fluid::sim::export::{self, ExportSettings, Exporter, Format};
+use wgpu_fluid::sim::scene::Fluid;
+use wgpu_fluid::sim::{Particle, Particles, Scene};
+
+fn particles() -> Particles {
+	let scene = Scene {
+		seed: Some(1),
+		fluids: vec![Fluid::Block {
+			min: Vector3::new(-30.0, -30.0, -30.0),
+			max: Vector3::new(30.0, 30.0, 30.0),
+			velocity: Vector3::new(1.0, 0.0, -0.5),
+		}],
+		..Scene::default()
+	};
+	let mut particles = Particles::new(&scene);
+	// densities and pressures are only filled in by a step
+	particles.update();
+	particles
+}
+
+fn round_trip(format: Format, particles: &[Particle]) -> Vec<Particle> {
+	let mut bytes = Vec::new();
+	export::write(format, particles, &mut bytes).unwrap();
+	export::read(format, bytes.as_slice()).unwrap()
+}
+
+fn assert_same(a: &Particle, b: &Particle, color_tolerance: f32) {
+	assert_eq!(a.position, b.position);
+	assert_eq!(a.velocity, b.velocity);
+	assert_eq!(a.density, b.density);
+	assert_eq!(a.pressure, b.pressure);
+	assert_eq!(a.mass, b.mass);
+	assert_eq!(a.radius, b.radius);
+	for (x, y) in a.color.iter().zip(b.color) {
+		assert!(
+			(x - y).abs() <= color_tolerance,
+			"color {:?} != {:?}",
+			a.color,
+			b.color
+		);
+	}
+}
+
+#[test]
+fn vtk_round_trip() {
+	let particles = particles();
+	let read = round_trip(Format::Vtk, particles.particles());
+	assert_eq!(read.len(), particles.particles().len());
+	for (a, b) in particles.particles().iter().zip(&read) {
+		assert_same(a, b, 0.0);
+	}
+}
+
+#[test]
+fn ply_round_trip() {
+	let particles = particles();
+	let read = round_trip(Format::Ply, particles.particles());
+	assert_eq!(read.len(), particles.particles().len());
+	// colors are stored as bytes
+	for (a, b) in particles.particles().iter().zip(&read) {
+		assert_same(a, b, 0.5 / 255.0);
+	}
+}
+
+#[test]
+fn csv_round_trip() {
+	let particles = particles();
+	let read = round_trip(Format::Csv, particles.particles());
+	assert_eq!(read.len(), particles.particles().len());
+	for (a, b) in particles.particles().iter().zip(&read) {
+		assert_same(a, b, 0.0);
+	}
+}
+
+#[test]
+fn empty_round_trip() {
+	for format in [Format::Vtk, Format::Ply, Format::Csv] {
+		assert!(round_trip(format, &[]).is_empty());
+	}
+}
+
+#[test]
+fn exporter_writes_every_interval() {
+	let dir = std::env::temp_dir().join(format!("wgpu_fluid_export_{}", std::process::id()));
+	let _ = std::fs::remove_dir_all(&dir);
+	let mut exporter = Exporter::new(ExportSettings {
+		format: Format::Ply,
+		dir: dir.clone(),
+		interval: 3,
+		surface: None,
+	})
+	.unwrap();
+
+	let mut particles = particles();
+	for _ in 0..7 {
+		exporter.frame(&particles).unwrap();
+		particles.update();
+	}
+
+	// frames 0, 3 and 6
+	let mut files = std::fs::read_dir(&dir)
+		.unwrap()
+		.map(|entry| entry.unwrap().file_name().into_string().unwrap())
+		.collect::<Vec<_>>();
+	files.sort();
+	assert_eq!(
+		files,
+		["frame_00000.ply", "frame_00001.ply", "frame_00002.ply"]
+	);
+
+	let file = std::fs::File::open(dir.join("frame_00002.ply")).unwrap();
+	let read = export::read(Format::Ply, file).unwrap();
+	assert_eq!(read.len(), particles.particles().len());
+
+	std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,156 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use wgpu_fluid::sim::emitter::{Emitter, Source};
+use wgpu_fluid::sim::scene::Fluid;
+use wgpu_fluid::sim::sink::{Region, Sink};
+use wgpu_fluid::sim::{Particles, Scene};
+
+// no fluid, forces or colliders unless a test adds them
+fn scene() -> Scene {
+	Scene {
+		seed: Some(1),
+		fluids: Vec::new(),
+		colliders: Vec::new(),
+		fields: Vec::new(),
+		..Scene::default()
+	}
+}
+
+// a row of particles along x from -95 to 95, spacing 10
+fn row() -> Scene {
+	Scene {
+		fluids: vec![Fluid::Block {
+			min: Vector3::new(-100.0, 0.0, 0.0),
+			max: Vector3::new(100.0, 10.0, 10.0),
+			velocity: Vector3::zero(),
+		}],
+		..scene()
+	}
+}
+
+fn drained(sink: Sink) -> Vec<f32> {
+	let mut particles = Particles::new(&Scene {
+		sinks: vec![sink],
+		..row()
+	});
+	assert_eq!(particles.particles().len(), 20);
+	particles.update_flow(0.1);
+	particles.particles().iter().map(|p| p.position.x).collect()
+}
+
+#[test]
+fn sinks_remove_particles() {
+	let behind_plane = drained(Sink::new(Region::Plane {
+		normal: Vector3::unit_x(),
+		offset: 0.0,
+	}));
+	assert!(behind_plane.len() == 10 && behind_plane.iter().all(|&x| x > 0.0));
+
+	let in_box = drained(Sink::new(Region::Box {
+		min: Vector3::new(-20.0, -1.0, -1.0),
+		max: Vector3::new(20.0, 11.0, 11.0),
+	}));
+	assert!(in_box.len() == 16 && in_box.iter().all(|&x| x.abs() > 20.0));
+
+	let far = drained(Sink::new(Region::Distance {
+		center: Vector3::zero(),
+		radius: 50.0,
+	}));
+	assert!(far.len() == 10 && far.iter().all(|&x| x.abs() < 50.0));
+
+	let mut disabled = Sink::new(Region::Distance {
+		center: Vector3::zero(),
+		radius: 0.0,
+	});
+	disabled.enabled = false;
+	assert_eq!(drained(disabled).len(), 20);
+}
+
+#[test]
+fn volume_source_refills() {
+	let mut particles = Particles::new(&Scene {
+		emitters: vec![Emitter::new(Source::Volume {
+			min: Vector3::new(0.0, 0.0, 0.0),
+			max: Vector3::new(30.0, 30.0, 30.0),
+			velocity: Vector3::new(1.0, 0.0, 0.0),
+		})],
+		..scene()
+	});
+	assert!(particles.is_open());
+	assert!(particles.update_flow(0.1));
+	assert_eq!(particles.particles().len(), 27);
+	assert!(particles
+		.particles()
+		.iter()
+		.all(|p| p.velocity == Vector3::new(1.0, 0.0, 0.0)));
+
+	// full, nothing to add
+	assert!(!particles.update_flow(0.1));
+
+	// a layer flows out and gets replaced
+	for p in particles.particles_mut() {
+		if p.position.x < 10.0 {
+			p.position.x -= 100.0;
+		}
+	}
+	assert!(particles.update_flow(0.1));
+	assert_eq!(particles.particles().len(), 36);
+}
+
+// along x from the origin, in layers of 5: the center and its 4 neighbors on the lattice
+fn nozzle(speed: f32, rate: f32) -> Emitter {
+	Emitter::new(Source::Nozzle {
+		center: Vector3::zero(),
+		direction: Vector3::unit_x(),
+		radius: 10.0,
+		speed,
+		rate,
+	})
+}
+
+#[test]
+fn nozzle_emits_at_its_rate() {
+	// over 10 units of time
+	let emitted = |speed: f32, rate: f32| {
+		let mut particles = Particles::new(&Scene {
+			emitters: vec![nozzle(speed, rate)],
+			..scene()
+		});
+		for _ in 0..80 {
+			particles.update_flow(0.125);
+		}
+		particles.particles().len()
+	};
+	for speed in [1.0, 5.0, 20.0] {
+		for rate in [2.0, 10.0, 40.0] {
+			// whole layers of 5 fit exactly
+			assert_eq!(
+				emitted(speed, rate),
+				(rate * 10.0) as usize,
+				"{rate} per time"
+			);
+		}
+	}
+	assert_eq!(emitted(5.0, 0.0), 0);
+}
+
+#[test]
+fn nozzle_keeps_flowing_into_a_sink() {
+	let mut particles = Particles::new(&Scene {
+		// packed at the lattice spacing
+		emitters: vec![nozzle(5.0, 2.5)],
+		sinks: vec![Sink::new(Region::Plane {
+			normal: -Vector3::unit_x(),
+			offset: -50.0,
+		})],
+		..scene()
+	});
+	let mut counts = Vec::new();
+	for _ in 0..40 {
+		particles.update();
+		counts.push(particles.particles().len());
+	}
+	assert!(particles.particles().iter().all(|p| p.position.x <= 50.0));
+	// the stream reaches the sink and the count levels off
+	assert!(counts[39] > 0 && counts[39] <= counts[20] + 5 * 5);
+}
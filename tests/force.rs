@@ -0,0 +1,109 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use wgpu_fluid::sim::force::{Field, ForceField};
+
+const DENSITY: f32 = 0.001;
+
+fn force(field: Field, position: Vector3<f32>, velocity: Vector3<f32>) -> Vector3<f32> {
+	ForceField::new(field).force(position, velocity, DENSITY, &mut Pcg32::seed_from_u64(1))
+}
+
+fn at(field: Field, position: Vector3<f32>) -> Vector3<f32> {
+	force(field, position, Vector3::zero())
+}
+
+#[test]
+fn gravity_is_the_same_acceleration_everywhere() {
+	let gravity = Field::Gravity {
+		acceleration: Vector3::new(0.0, -0.05, 0.0),
+	};
+	for position in [Vector3::zero(), Vector3::new(100.0, -50.0, 20.0)] {
+		// per unit volume, so it divides back to the acceleration
+		let a = at(gravity, position) / DENSITY;
+		assert!(
+			(a - Vector3::new(0.0, -0.05, 0.0)).magnitude() < 1e-6,
+			"{a:?}"
+		);
+	}
+}
+
+#[test]
+fn point_attracts_and_repels() {
+	let center = Vector3::new(10.0, 0.0, 0.0);
+	let point = |strength| Field::Point { center, strength };
+	let position = Vector3::new(10.0, 20.0, 0.0);
+
+	let pull = at(point(0.5), position);
+	assert!(
+		(pull - Vector3::new(0.0, -10.0, 0.0)).magnitude() < 1e-6,
+		"{pull:?}"
+	);
+	// a spring, twice as far is twice as strong
+	let far = at(point(0.5), Vector3::new(10.0, 40.0, 0.0));
+	assert!((far.magnitude() / pull.magnitude() - 2.0).abs() < 1e-5);
+
+	let push = at(point(-0.5), position);
+	assert!(push.y > 0.0 && push.x == 0.0, "{push:?}");
+	assert_eq!(at(point(0.5), center), Vector3::zero());
+}
+
+#[test]
+fn vortex_swirls_around_its_axis() {
+	let vortex = Field::Vortex {
+		center: Vector3::new(0.0, 5.0, 0.0),
+		axis: Vector3::new(0.0, 3.0, 0.0),
+		strength: 1.0,
+	};
+	// counterclockwise seen from the tip of the axis, like the right hand rule
+	let f = at(vortex, Vector3::new(10.0, 0.0, 0.0));
+	assert!(
+		(f - Vector3::new(0.0, 0.0, -10.0)).magnitude() < 1e-5,
+		"{f:?}"
+	);
+	let f = at(vortex, Vector3::new(0.0, 30.0, 10.0));
+	assert!(
+		(f - Vector3::new(10.0, 0.0, 0.0)).magnitude() < 1e-5,
+		"{f:?}"
+	);
+	// nothing on the axis
+	assert_eq!(at(vortex, Vector3::new(0.0, -20.0, 0.0)), Vector3::zero());
+}
+
+#[test]
+fn drag_opposes_the_velocity() {
+	let drag = Field::Drag { coefficient: 0.1 };
+	let velocity = Vector3::new(3.0, -4.0, 0.0);
+	let f = force(drag, Vector3::new(50.0, 0.0, 0.0), velocity);
+	assert!((f + velocity * 0.1).magnitude() < 1e-6, "{f:?}");
+	assert_eq!(at(drag, Vector3::new(50.0, 0.0, 0.0)), Vector3::zero());
+}
+
+#[test]
+fn noise_kicks_in_every_direction() {
+	let noise = ForceField::new(Field::Noise { amplitude: 2.0 });
+	let mut rng = Pcg32::seed_from_u64(1);
+	let kicks = (0..10_000)
+		.map(|_| noise.force(Vector3::zero(), Vector3::zero(), DENSITY, &mut rng))
+		.collect::<Vec<_>>();
+	let mean = kicks.iter().sum::<Vector3<f32>>() / kicks.len() as f32;
+	assert!(mean.magnitude() < 0.1, "{mean:?}");
+	// standard deviation `amplitude` per axis
+	let variance = kicks.iter().map(|f| f.x * f.x).sum::<f32>() / kicks.len() as f32;
+	assert!((variance.sqrt() - 2.0).abs() < 0.1, "{variance}");
+}
+
+#[test]
+fn disabled_fields_do_nothing() {
+	let field = ForceField::disabled(Field::Gravity {
+		acceleration: Vector3::new(0.0, -0.05, 0.0),
+	});
+	let f = field.force(
+		Vector3::zero(),
+		Vector3::zero(),
+		DENSITY,
+		&mut Pcg32::seed_from_u64(1),
+	);
+	assert_eq!(f, Vector3::zero());
+}
@@ -0,0 +1,170 @@
+#![cfg(feature = "render")]
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use wgpu_fluid::gpu::GpuSolver;
+use wgpu_fluid::render::ParticleInstances;
+use wgpu_fluid::sim::collider::{Collider, Shape};
+use wgpu_fluid::sim::force::{Field, ForceField};
+use wgpu_fluid::sim::scene::Fluid;
+use wgpu_fluid::sim::{Integrator, Particles, Scene, Solver};
+
+// a software adapter that can run compute shaders, none on machines without one
+fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+	let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+		backends: wgpu::Backends::all(),
+		dx12_shader_compiler: wgpu::Dx12Compiler::default(),
+	});
+	let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+		power_preference: wgpu::PowerPreference::default(),
+		compatible_surface: None,
+		force_fallback_adapter: true,
+	}))?;
+	if !adapter
+		.get_downlevel_capabilities()
+		.flags
+		.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+	{
+		return None;
+	}
+	pollster::block_on(adapter.request_device(
+		&wgpu::DeviceDescriptor {
+			label: None,
+			features: wgpu::Features::empty(),
+			limits: wgpu::Limits::downlevel_defaults(),
+		},
+		None,
+	))
+	.ok()
+}
+
+// what the compute shader solver covers: no random forces
+// and one symplectic Euler step per frame
+fn scene() -> Scene {
+	let mut scene = Scene {
+		seed: Some(1),
+		fluids: vec![Fluid::Block {
+			min: Vector3::new(-50.0, -50.0, -50.0),
+			max: Vector3::new(50.0, 50.0, 50.0),
+			velocity: Vector3::new(0.1, 0.0, 0.0),
+		}],
+		colliders: Vec::new(),
+		fields: vec![ForceField::new(Field::Gravity {
+			acceleration: Vector3::new(0.0, -0.05, 0.0),
+		})],
+		..Scene::default()
+	};
+	scene.solver.solver = Solver::StateEquation;
+	scene.solver.integrator = Integrator::SymplecticEuler;
+	scene.solver.timestep.adaptive = false;
+	scene
+}
+
+// steps both solvers for 20 frames and compares the particles
+fn compare(scene: &Scene, n: usize) {
+	let Some((device, queue)) = device() else {
+		eprintln!("no software adapter with compute shaders, skipped");
+		return;
+	};
+	let mut cpu = Particles::new(scene);
+	let mut gpu = Particles::new(scene);
+	assert_eq!(cpu.particles().len(), n);
+
+	let instances = ParticleInstances::new(&device, n);
+	let mut solver = GpuSolver::new(&device, &queue, &gpu, &instances);
+	for _ in 0..20 {
+		cpu.update();
+		solver.update(&device, &queue, &gpu, None);
+	}
+	solver.download(&device, &queue, &mut gpu);
+
+	// the fluid has moved, so the comparison means something
+	let start = Particles::new(scene);
+	let moved = cpu
+		.particles()
+		.iter()
+		.zip(start.particles())
+		.map(|(a, b)| (a.position - b.position).magnitude())
+		.fold(0.0, f32::max);
+	assert!(moved > 1.0, "{moved}");
+
+	for (a, b) in cpu.particles().iter().zip(gpu.particles()) {
+		assert!(
+			(a.position - b.position).magnitude() < 1e-3,
+			"{:?} {:?}",
+			a.position,
+			b.position
+		);
+		assert!(
+			(a.velocity - b.velocity).magnitude() < 1e-3,
+			"{:?} {:?}",
+			a.velocity,
+			b.velocity
+		);
+		assert!(
+			(a.density - b.density).abs() < 1e-3 * scene.material.rest_density,
+			"{} {}",
+			a.density,
+			b.density
+		);
+	}
+}
+
+#[test]
+fn gpu_solver_matches_cpu_solver() {
+	compare(&scene(), 1000);
+}
+
+#[test]
+fn gpu_solver_resolves_colliders() {
+	let mut scene = scene();
+	// a box the block presses against, a wall through it and a ball inside it
+	let mut walls = Collider::new(Shape::Box {
+		min: Vector3::new(-52.0, -52.0, -52.0),
+		max: Vector3::new(100.0, 100.0, 100.0),
+	});
+	walls.restitution = 0.5;
+	walls.friction = 0.3;
+	scene.colliders = vec![
+		walls,
+		Collider::new(Shape::Plane {
+			normal: Vector3::new(-2.0, 0.0, -1.0),
+			offset: -40.0,
+		}),
+		Collider::new(Shape::Sphere {
+			center: Vector3::new(0.0, 0.0, 0.0),
+			radius: 15.0,
+		}),
+	];
+	compare(&scene, 1000);
+
+	// and every particle ends up outside of them
+	let Some((device, queue)) = device() else {
+		return;
+	};
+	let mut particles = Particles::new(&scene);
+	let instances = ParticleInstances::new(&device, particles.particles().len());
+	let mut solver = GpuSolver::new(&device, &queue, &particles, &instances);
+	for _ in 0..20 {
+		solver.update(&device, &queue, &particles, None);
+	}
+	solver.download(&device, &queue, &mut particles);
+	for p in particles.particles() {
+		for collider in &scene.colliders {
+			let (distance, _) = collider.distance(p.position);
+			assert!(distance >= p.radius - 1e-3, "{:?} {distance}", p.position);
+		}
+	}
+}
+
+#[test]
+fn gpu_solver_scales_to_large_scenes() {
+	// enough buckets for every thread of the prefix sum to take several
+	let mut scene = scene();
+	scene.max_particles = 30_000;
+	scene.fluids = vec![Fluid::Block {
+		min: Vector3::new(-120.0, -120.0, -120.0),
+		max: Vector3::new(120.0, 120.0, 120.0),
+		velocity: Vector3::new(0.1, 0.0, 0.0),
+	}];
+	compare(&scene, 13_824);
+}
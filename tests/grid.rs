@@ -0,0 +1,106 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use std::path::Path;
+use std::time::Instant;
+use wgpu_fluid::sim::grid::Grid;
+use wgpu_fluid::sim::{Particles, Scene};
+
+// neighbor lists from the grid and from comparing every pair
+fn neighbors(positions: &[Vector3<f32>], radius: f32) -> (Vec<Vec<usize>>, Vec<Vec<usize>>) {
+	let mut grid = Grid::new(radius);
+	grid.build(positions.iter().copied());
+	let within = |x: Vector3<f32>, j: usize| (positions[j] - x).magnitude2() <= radius * radius;
+
+	let from_grid = positions
+		.iter()
+		.map(|&x| {
+			let mut list = Vec::new();
+			grid.for_each_candidate(x, |j| {
+				if within(x, j) {
+					list.push(j);
+				}
+			});
+			list.sort_unstable();
+			list
+		})
+		.collect();
+	let brute_force = positions
+		.iter()
+		.map(|&x| (0..positions.len()).filter(|&j| within(x, j)).collect())
+		.collect();
+	(from_grid, brute_force)
+}
+
+#[test]
+fn grid_finds_the_same_neighbors_as_brute_force() {
+	let mut rng = Pcg32::seed_from_u64(1);
+	// around the origin, so cells on both sides of zero
+	let positions = (0..2000)
+		.map(|_| {
+			Vector3::new(
+				rng.gen_range(-100.0..100.0),
+				rng.gen_range(-100.0..100.0),
+				rng.gen_range(-100.0..100.0),
+			)
+		})
+		.collect::<Vec<_>>();
+	let (from_grid, brute_force) = neighbors(&positions, 16.0);
+	assert_eq!(from_grid, brute_force);
+	// not just every particle alone
+	assert!(brute_force.iter().map(Vec::len).sum::<usize>() > 2 * positions.len());
+}
+
+#[test]
+fn grid_rejects_hash_collisions() {
+	let mut rng = Pcg32::seed_from_u64(2);
+	// 50 particles get 100 buckets for about 15^3 cells, so far apart cells share buckets
+	let positions = (0..50)
+		.map(|_| {
+			Vector3::new(
+				rng.gen_range(-40.0..40.0),
+				rng.gen_range(-40.0..40.0),
+				rng.gen_range(-40.0..40.0),
+			)
+		})
+		.collect::<Vec<_>>();
+	let (from_grid, brute_force) = neighbors(&positions, 6.0);
+	assert_eq!(from_grid, brute_force);
+
+	// candidates only ever come from the 3x3x3 cells around the query
+	let mut grid = Grid::new(6.0);
+	grid.build(positions.iter().copied());
+	for &x in &positions {
+		grid.for_each_candidate(x, |j| {
+			let d = positions[j] - x;
+			assert!(d.x.abs() < 12.0 && d.y.abs() < 12.0 && d.z.abs() < 12.0);
+		});
+	}
+}
+
+// cargo test --release --test grid -- --ignored --nocapture
+#[test]
+#[ignore]
+fn hundred_thousand_particles() {
+	let scene =
+		Scene::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/large.ron")).unwrap();
+	let mut particles = Particles::new(&scene);
+	assert!(particles.particles().len() >= 100_000);
+
+	let start = Instant::now();
+	let frames = 5;
+	for _ in 0..frames {
+		particles.update();
+	}
+	let per_frame = start.elapsed() / frames;
+	let substeps = particles.stats().substeps;
+	println!(
+		"{} particles: {per_frame:?} per frame, {substeps} substeps",
+		particles.particles().len()
+	);
+	assert!(particles
+		.particles()
+		.iter()
+		.all(|p| p.position.x.is_finite()));
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "render")]
+use wgpu_fluid::render::Headless;
+use wgpu_fluid::sim::Scene;
+
+#[test]
+fn renders_numbered_frames_on_a_software_adapter() {
+	let scene = Scene {
+		seed: Some(1),
+		..Scene::default()
+	};
+	let mut headless = match pollster::block_on(Headless::new(&scene, 64, 48, true)) {
+		Ok(headless) => headless,
+		Err(e) => {
+			eprintln!("{e:#}, skipped");
+			return;
+		}
+	};
+
+	let dir = std::env::temp_dir().join(format!("wgpu_fluid_headless_{}", std::process::id()));
+	let _ = std::fs::remove_dir_all(&dir);
+	headless.run(2, &dir).unwrap();
+
+	for frame in ["frame_00000.png", "frame_00001.png"] {
+		let file = std::fs::File::open(dir.join(frame)).unwrap();
+		let reader = png::Decoder::new(file).read_info().unwrap();
+		let info = reader.info();
+		assert_eq!((info.width, info.height), (64, 48));
+		assert_eq!(info.color_type, png::ColorType::Rgba);
+	}
+	assert!(!dir.join("frame_00002.png").exists());
+	std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use wgpu_fluid::sim::force::{Field, ForceField};
+use wgpu_fluid::sim::{Integrator, Particles};
+
+// a single particle on a spring to the origin, a harmonic oscillator with omega dt = 0.2
+fn oscillator(integrator: Integrator) -> (Particles, f32) {
+	let mut scene = common::block(Vector3::new(0.0, 0.0, 0.0), Vector3::new(10.0, 10.0, 10.0));
+	scene.fields = vec![ForceField::new(Field::Point {
+		center: Vector3::zero(),
+		strength: 1e-5,
+	})];
+	scene.solver.integrator = integrator;
+	let mut particles = Particles::new(&scene);
+	assert_eq!(particles.particles().len(), 1);
+
+	// the force is per unit volume, so omega^2 = strength / density
+	particles.update_grid();
+	particles.update_pressure();
+	let omega = (1e-5 / particles.particles()[0].density).sqrt();
+	let timestep = particles.timestep_mut();
+	timestep.adaptive = false;
+	timestep.frame_time = 0.2 / omega;
+	particles.particles_mut()[0].velocity = Vector3::new(0.0, 0.0, 0.0);
+	(particles, omega)
+}
+
+// largest relative deviation from the initial energy over about 5 periods
+fn energy_drift(integrator: Integrator) -> f32 {
+	let (mut particles, omega) = oscillator(integrator);
+	let energy = |particles: &Particles| {
+		let p = particles.particles()[0];
+		0.5 * p.velocity.magnitude2() + 0.5 * omega * omega * p.position.magnitude2()
+	};
+	let start = energy(&particles);
+	let mut drift = 0.0f32;
+	for _ in 0..160 {
+		particles.update();
+		drift = drift.max((energy(&particles) / start - 1.0).abs());
+	}
+	drift
+}
+
+#[test]
+fn higher_order_integrators_conserve_energy_better() {
+	let euler = energy_drift(Integrator::SymplecticEuler);
+	let leapfrog = energy_drift(Integrator::Leapfrog);
+	let rk4 = energy_drift(Integrator::Rk4);
+	// symplectic euler keeps the energy bounded, within about omega dt / 2
+	assert!(euler < 0.15, "{euler}");
+	assert!(
+		leapfrog < 0.25 * euler,
+		"leapfrog {leapfrog}, euler {euler}"
+	);
+	assert!(rk4 < 0.25 * euler, "rk4 {rk4}, euler {euler}");
+}
+
+#[test]
+fn integrators_follow_the_exact_solution() {
+	// x(t) = x0 cos(omega t) from rest
+	for integrator in [
+		Integrator::SymplecticEuler,
+		Integrator::Leapfrog,
+		Integrator::Rk4,
+	] {
+		let (mut particles, omega) = oscillator(integrator);
+		let x0 = particles.particles()[0].position;
+		for _ in 0..30 {
+			particles.update();
+		}
+		let expected = x0 * (omega * particles.time() as f32).cos();
+		let error = (particles.particles()[0].position - expected).magnitude() / x0.magnitude();
+		assert!(error < 0.2, "{}: {error}", integrator.name());
+	}
+}
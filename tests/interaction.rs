@@ -0,0 +1,140 @@
+mod common;
+
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use wgpu_fluid::sim::force::{Field, ForceField};
+use wgpu_fluid::sim::{Particle, Particles, Ray};
+
+// lattice of spacing 10 around the origin
+fn block() -> Particles {
+	Particles::new(&common::cube(50.0))
+}
+
+// through the column of particles closest to the z axis
+fn down_the_z_axis(particles: &Particles) -> Ray {
+	let p = particles
+		.particles()
+		.iter()
+		.min_by(|a, b| {
+			let d = |p: &&Particle| p.position.x.abs() + p.position.y.abs();
+			d(a).total_cmp(&d(b))
+		})
+		.unwrap();
+	Ray::new(
+		Vector3::new(p.position.x, p.position.y, 200.0),
+		Vector3::new(0.0, 0.0, -3.0),
+	)
+}
+
+#[test]
+fn pick_the_nearest_particle() {
+	let particles = block();
+	let front = particles
+		.particles()
+		.iter()
+		.map(|p| p.position.z)
+		.fold(f32::MIN, f32::max);
+	// to the center of the front particle in the column
+	let t = particles.pick(&down_the_z_axis(&particles)).unwrap();
+	assert!((t - (200.0 - front)).abs() < 1e-3, "hit at {t}");
+
+	let miss = Ray::new(Vector3::new(0.0, 200.0, 200.0), Vector3::unit_z());
+	assert!(particles.pick(&miss).is_none());
+}
+
+#[test]
+fn drag_moves_the_grab() {
+	let mut particles = block();
+	let ray = down_the_z_axis(&particles);
+	let mut grab = particles.grab(&ray, 15.0).unwrap();
+
+	// moving the cursor up by 20 moves the grabbed particles up by 20 over one step
+	let moved = Ray::new(ray.origin + Vector3::new(0.0, 20.0, 0.0), ray.direction);
+	particles.drag(&mut grab, &moved);
+	let dt = particles.timestep().frame_time;
+	let center = ray.at(grab.distance);
+	let mut dragged = 0;
+	for p in particles.particles() {
+		if (p.position - center).magnitude() < 15.0 {
+			assert!((p.velocity * dt - Vector3::new(0.0, 20.0, 0.0)).magnitude() < 1e-3);
+			dragged += 1;
+		} else {
+			assert_eq!(p.velocity, Vector3::zero());
+		}
+	}
+	assert!(dragged > 1);
+}
+
+#[test]
+fn drag_follows_the_grab_when_particles_are_removed() {
+	let mut particles = block();
+	let ray = down_the_z_axis(&particles);
+	let mut grab = particles.grab(&ray, 15.0).unwrap();
+	let center = ray.at(grab.distance);
+	let grabbed = particles
+		.particles()
+		.iter()
+		.filter(|p| (p.position - center).magnitude() < 15.0)
+		.count();
+
+	// the lattice starts at this corner, so every index after it shifts
+	assert!(particles.erase(Vector3::new(-45.0, -45.0, -45.0), 1.0) > 0);
+	particles.drag(&mut grab, &ray);
+	let moving = particles
+		.particles()
+		.iter()
+		.filter(|p| p.velocity != Vector3::zero())
+		.count();
+	// held still at the grab point, only the grabbed particles are touched
+	for p in particles.particles() {
+		if (p.position - center).magnitude() >= 15.0 {
+			assert_eq!(p.velocity, Vector3::zero());
+		}
+	}
+	assert!(moving <= grabbed);
+
+	// and they are still carried along
+	let moved = Ray::new(ray.origin + Vector3::new(0.0, 20.0, 0.0), ray.direction);
+	particles.drag(&mut grab, &moved);
+	let dt = particles.timestep().frame_time;
+	let dragged = particles
+		.particles()
+		.iter()
+		.filter(|p| (p.velocity * dt - Vector3::new(0.0, 20.0, 0.0)).magnitude() < 1e-3)
+		.count();
+	assert_eq!(dragged, grabbed);
+}
+
+#[test]
+fn spawn_and_erase() {
+	let mut particles = block();
+	let n = particles.particles().len();
+	let center = Vector3::new(0.0, 200.0, 0.0);
+	let spawned = particles.spawn_ball(center, 15.0);
+	// the center, 6 faces and 12 edges of the lattice
+	assert_eq!(spawned, 19);
+	assert_eq!(particles.particles().len(), n + spawned);
+
+	assert_eq!(particles.erase(center, 15.0), spawned);
+	assert_eq!(particles.particles().len(), n);
+}
+
+#[test]
+fn brush_pushes_and_pulls() {
+	let brush = |strength| {
+		ForceField::new(Field::Brush {
+			center: Vector3::zero(),
+			radius: 10.0,
+			strength,
+		})
+	};
+	let at = |field: &ForceField, x: f32| {
+		let position = Vector3::new(x, 0.0, 0.0);
+		field.force(position, Vector3::zero(), 1.0, &mut rand::thread_rng())
+	};
+	let push = brush(1.0);
+	assert!(at(&push, 2.0).x > at(&push, 8.0).x);
+	assert!(at(&push, 8.0).x > 0.0);
+	assert_eq!(at(&push, 12.0), Vector3::zero());
+	assert!(at(&brush(-1.0), 2.0).x < 0.0);
+}
@@ -0,0 +1,229 @@
+use std::path::Path;
+use wgpu_fluid::sim::collider::Shape;
+use wgpu_fluid::sim::emitter::{Emitter, Source};
+use wgpu_fluid::sim::eos::EquationOfState;
+use wgpu_fluid::sim::force::Field;
+use wgpu_fluid::sim::particle::{SurfaceTension, Viscosity};
+use wgpu_fluid::sim::scene::{Fluid, Material, SolverSettings};
+use wgpu_fluid::sim::sink::Region;
+use wgpu_fluid::sim::{Integrator, Particles, Scene, Solver};
+
+// every fluid, emitter, sink, collider and field
+const EVERYTHING: &str = r#"(
+	max_particles: 5000,
+	seed: Some(7),
+	material: (
+		rest_density: 0.002,
+		viscosity: Morris(kinematic: 5.0),
+		spacing: 8.0,
+		color: Some((0.1, 0.2, 0.3)),
+		surface_tension: Akinci(coefficient: 0.05),
+		adhesion: 0.1,
+	),
+	fluids: [
+		Block(min: (x: -40.0, y: -40.0, z: -40.0), max: (x: 0.0, y: 0.0, z: 0.0)),
+		Sphere(center: (x: 40.0, y: 40.0, z: 0.0), radius: 20.0, velocity: (x: 1.0, y: 0.0, z: 0.0)),
+		Cloud(center: (x: 0.0, y: 80.0, z: 0.0), deviation: (x: 5.0, y: 5.0, z: 5.0), count: 100),
+	],
+	emitters: [
+		(source: Nozzle(center: (x: 0.0, y: 0.0, z: 0.0), direction: (x: 1.0, y: 0.0, z: 0.0), radius: 8.0, speed: 2.0, rate: 4.0)),
+		(source: Volume(min: (x: -10.0, y: -10.0, z: -10.0), max: (x: 10.0, y: 10.0, z: 10.0)), enabled: false),
+	],
+	sinks: [
+		(region: Plane(normal: (x: 0.0, y: 1.0, z: 0.0), offset: -100.0)),
+		(region: Box(min: (x: 90.0, y: -10.0, z: -10.0), max: (x: 100.0, y: 10.0, z: 10.0))),
+		(region: Distance(center: (x: 0.0, y: 0.0, z: 0.0), radius: 500.0), enabled: false),
+	],
+	colliders: [
+		(shape: Box(min: (x: -100.0, y: -100.0, z: -100.0), max: (x: 100.0, y: 100.0, z: 100.0)), restitution: 0.5, friction: 0.0),
+		(shape: Plane(normal: (x: 0.0, y: 1.0, z: 0.0), offset: -60.0)),
+		(shape: Sphere(center: (x: 0.0, y: -60.0, z: 0.0), radius: 30.0)),
+	],
+	fields: [
+		(field: Gravity(acceleration: (x: 0.0, y: -0.05, z: 0.0))),
+		(field: Point(center: (x: 0.0, y: 0.0, z: 0.0), strength: 0.000001), enabled: false),
+		(field: Vortex(center: (x: 0.0, y: 0.0, z: 0.0), axis: (x: 0.0, y: 1.0, z: 0.0), strength: 0.000002)),
+		(field: Drag(coefficient: 0.000001)),
+		(field: Noise(amplitude: 0.00001)),
+		(field: Brush(center: (x: 0.0, y: 0.0, z: 0.0), radius: 30.0, strength: 0.1)),
+	],
+	camera: (target: (x: 0.0, y: 10.0, z: 0.0), yaw: 30.0, pitch: 20.0, distance: 400.0, fovy: 50.0),
+	solver: (
+		solver: Dfsph,
+		integrator: Leapfrog,
+		h: 16.0,
+		eos: Tait(speed_of_sound: 12.0, gamma: 7.0),
+		other_eos: Some(IdealGas(gas_const: 40.0)),
+		timestep: (frame_time: 0.25, adaptive: false),
+		pbf: (iterations: 3),
+		dfsph: (density_tolerance: 0.002),
+		velocity: Some((vorticity: 0.001, xsph: 0.05)),
+	),
+	surface: (cell_size: 0.5),
+	export: Some((format: Csv, dir: "export", interval: 4, surface: Some(()))),
+	visualization: (attribute: Some(Vorticity), colormap: Turbo, range: Some((0.0, 1.0))),
+)"#;
+
+#[test]
+fn scene_with_every_variant_parses() {
+	let scene: Scene = ron::from_str(EVERYTHING).unwrap();
+	assert_eq!(scene.max_particles, 5000);
+	assert_eq!(scene.seed, Some(7));
+	assert!(matches!(
+		scene.fluids[..],
+		[
+			Fluid::Block { .. },
+			Fluid::Sphere { .. },
+			Fluid::Cloud { count: 100, .. }
+		]
+	));
+	assert!(matches!(
+		scene.emitters[..],
+		[
+			Emitter {
+				source: Source::Nozzle { rate, .. },
+				enabled: true,
+				..
+			},
+			Emitter {
+				source: Source::Volume { .. },
+				enabled: false,
+				..
+			},
+		] if rate == 4.0
+	));
+	let regions = scene.sinks.iter().map(|s| s.region).collect::<Vec<_>>();
+	assert!(matches!(
+		regions[..],
+		[
+			Region::Plane { .. },
+			Region::Box { .. },
+			Region::Distance { .. }
+		]
+	));
+	assert!(!scene.sinks[2].enabled);
+	let shapes = scene.colliders.iter().map(|c| c.shape).collect::<Vec<_>>();
+	assert!(matches!(
+		shapes[..],
+		[Shape::Box { .. }, Shape::Plane { .. }, Shape::Sphere { .. }]
+	));
+	assert_eq!(scene.colliders[0].restitution, 0.5);
+	let fields = scene.fields.iter().map(|f| f.field).collect::<Vec<_>>();
+	assert!(matches!(
+		fields[..],
+		[
+			Field::Gravity { .. },
+			Field::Point { .. },
+			Field::Vortex { .. },
+			Field::Drag { .. },
+			Field::Noise { .. },
+			Field::Brush { .. },
+		]
+	));
+	assert!(!scene.fields[1].enabled);
+
+	let solver = scene.solver;
+	assert_eq!(solver.solver, Solver::Dfsph);
+	assert_eq!(solver.integrator, Integrator::Leapfrog);
+	assert!(
+		matches!(solver.eos, EquationOfState::Tait { speed_of_sound, .. } if speed_of_sound == 12.0)
+	);
+	assert!(!solver.timestep.adaptive);
+	assert_eq!(solver.pbf.iterations, 3);
+	assert_eq!(solver.dfsph.density_tolerance, 0.002);
+	assert_eq!(scene.export.as_ref().unwrap().interval, 4);
+
+	// and it runs
+	let mut particles = Particles::new(&scene);
+	assert!(particles.particles().len() > 100);
+	particles.update();
+}
+
+#[test]
+fn every_solver_setting_parses() {
+	for (text, solver) in [
+		("StateEquation", Solver::StateEquation),
+		("Pbf", Solver::Pbf),
+		("Dfsph", Solver::Dfsph),
+	] {
+		let settings: SolverSettings = ron::from_str(&format!("(solver: {text})")).unwrap();
+		assert_eq!(settings.solver, solver);
+	}
+	for (text, integrator) in [
+		("SymplecticEuler", Integrator::SymplecticEuler),
+		("Leapfrog", Integrator::Leapfrog),
+		("Rk4", Integrator::Rk4),
+	] {
+		let settings: SolverSettings = ron::from_str(&format!("(integrator: {text})")).unwrap();
+		assert_eq!(settings.integrator, integrator);
+	}
+	let settings: SolverSettings = ron::from_str("(eos: IdealGas(gas_const: 20.0))").unwrap();
+	assert!(matches!(settings.eos, EquationOfState::IdealGas { gas_const } if gas_const == 20.0));
+}
+
+#[test]
+fn every_material_model_parses() {
+	let viscosity = |text: &str| {
+		ron::from_str::<Material>(&format!("(viscosity: {text})"))
+			.unwrap()
+			.viscosity
+	};
+	assert_eq!(
+		viscosity("Laplacian(dynamic: 0.02)"),
+		Viscosity::Laplacian { dynamic: 0.02 }
+	);
+	assert_eq!(
+		viscosity("Artificial(alpha: 0.1, beta: 0.2)"),
+		Viscosity::Artificial {
+			alpha: 0.1,
+			beta: 0.2
+		}
+	);
+	assert_eq!(
+		viscosity("Morris(kinematic: 10.0)"),
+		Viscosity::Morris { kinematic: 10.0 }
+	);
+	assert_eq!(
+		viscosity("Implicit(kinematic: 5000.0, tolerance: 0.0001, max_iterations: 100)"),
+		Viscosity::Implicit {
+			kinematic: 5000.0,
+			tolerance: 0.0001,
+			max_iterations: 100
+		}
+	);
+
+	let tension = |text: &str| {
+		ron::from_str::<Material>(&format!("(surface_tension: {text})"))
+			.unwrap()
+			.surface_tension
+	};
+	assert_eq!(tension("None"), SurfaceTension::None);
+	assert_eq!(
+		tension("Csf(coefficient: 0.001, threshold: 0.1)"),
+		SurfaceTension::Csf {
+			coefficient: 0.001,
+			threshold: 0.1
+		}
+	);
+}
+
+#[test]
+fn shipped_scenes_load() {
+	let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+	let mut count = 0;
+	for entry in std::fs::read_dir(dir).unwrap() {
+		let path = entry.unwrap().path();
+		let scene = Scene::load(&path).unwrap_or_else(|e| panic!("{e:#}"));
+		assert!(!scene.fluids.is_empty() || !scene.emitters.is_empty());
+		count += 1;
+	}
+	assert!(count >= 4);
+}
+
+#[test]
+fn unknown_variants_are_rejected() {
+	assert!(ron::from_str::<Scene>("(fluids: [Cube(size: 1.0)])").is_err());
+	assert!(ron::from_str::<Scene>("(solver: (solver: Flip))").is_err());
+	let e = ron::from_str::<Scene>("(colliders: [(shape: Cylinder(radius: 1.0))])").unwrap_err();
+	assert!(e.to_string().contains("Cylinder"), "{e}");
+}
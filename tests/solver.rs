@@ -0,0 +1,71 @@
+mod common;
+
+use cgmath::Vector3;
+use wgpu_fluid::sim::collider::{Collider, Shape};
+use wgpu_fluid::sim::force::{Field, ForceField};
+use wgpu_fluid::sim::{Particles, Scene, Solver};
+
+// an 8 x 4 x 8 block resting on the floor of a box, under gravity
+fn resting(solver: Solver) -> Scene {
+	let mut scene = common::block(
+		Vector3::new(-40.0, -40.0, -40.0),
+		Vector3::new(40.0, 0.0, 40.0),
+	);
+	scene.colliders = vec![Collider::new(Shape::Box {
+		min: Vector3::new(-40.0, -40.0, -40.0),
+		max: Vector3::new(40.0, 100.0, 40.0),
+	})];
+	scene.fields = vec![ForceField::new(Field::Gravity {
+		acceleration: Vector3::new(0.0, -0.05, 0.0),
+	})];
+	scene.solver.solver = solver;
+	scene
+}
+
+// largest average density error over 100 frames, after the first 20 settled the block
+fn settled_error(scene: &Scene) -> f32 {
+	let mut particles = Particles::new(scene);
+	let mut worst = 0.0;
+	for frame in 0..100 {
+		particles.update();
+		if frame >= 20 {
+			worst = particles.stats().density_error.max(worst);
+		}
+	}
+	worst
+}
+
+#[test]
+fn pbf_keeps_a_resting_block_at_rest_density() {
+	let scene = resting(Solver::Pbf);
+	let error = settled_error(&scene);
+	assert!(error < 0.005, "{error}");
+
+	// the density constraint converges with its iterations
+	let mut rough = scene.clone();
+	rough.solver.pbf.iterations = 1;
+	let rough = settled_error(&rough);
+	assert!(
+		error < 0.5 * rough,
+		"{error} with 4 iterations, {rough} with 1"
+	);
+}
+
+#[test]
+fn dfsph_solves_down_to_its_tolerance() {
+	for tolerance in [0.001, 0.0001] {
+		let mut scene = resting(Solver::Dfsph);
+		scene.solver.dfsph.density_tolerance = tolerance;
+		let error = settled_error(&scene);
+		assert!(error <= tolerance, "{error} over {tolerance}");
+	}
+
+	// and reports the iterations it took, within the caps
+	let mut particles = Particles::new(&resting(Solver::Dfsph));
+	for _ in 0..20 {
+		particles.update();
+		let stats = particles.stats();
+		assert!((2..=100).contains(&stats.density_iterations), "{stats:?}");
+		assert!(stats.divergence_iterations <= 100, "{stats:?}");
+	}
+}
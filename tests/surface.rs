@@ -0,0 +1,85 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use std::collections::HashMap;
+use wgpu_fluid::sim::scene::Fluid;
+use wgpu_fluid::sim::surface::{Surface, TriangleMesh};
+use wgpu_fluid::sim::{Particles, Scene};
+
+const RADIUS: f32 = 40.0;
+
+fn sphere(center: Vector3<f32>) -> Particles {
+	Particles::new(&Scene {
+		seed: Some(1),
+		fluids: vec![Fluid::Sphere {
+			center,
+			radius: RADIUS,
+			velocity: Vector3::zero(),
+		}],
+		..Scene::default()
+	})
+}
+
+fn triangles(mesh: &TriangleMesh) -> impl Iterator<Item = [Vector3<f32>; 3]> + '_ {
+	mesh.indices
+		.chunks_exact(3)
+		.map(|t| [t[0], t[1], t[2]].map(|i| mesh.positions[i as usize]))
+}
+
+#[test]
+fn surface_is_closed() {
+	let particles = sphere(Vector3::zero());
+	let mesh = Surface::default().reconstruct(particles.particles());
+	assert!(!mesh.indices.is_empty());
+
+	// every directed edge is matched by exactly one edge running the other way
+	let mut edges = HashMap::new();
+	for t in mesh.indices.chunks_exact(3) {
+		for k in 0..3 {
+			*edges.entry((t[k], t[(k + 1) % 3])).or_insert(0) += 1;
+		}
+	}
+	for (&(a, b), &count) in &edges {
+		assert_eq!(count, 1, "edge {a} {b} is used {count} times");
+		assert_eq!(edges.get(&(b, a)), Some(&1), "edge {a} {b} is on a crack");
+	}
+}
+
+#[test]
+fn surface_faces_outward() {
+	let center = Vector3::new(10.0, -20.0, 5.0);
+	let particles = sphere(center);
+	let mesh = Surface::default().reconstruct(particles.particles());
+
+	for [a, b, c] in triangles(&mesh) {
+		let normal = (b - a).cross(c - a);
+		assert!(normal.dot((a + b + c) / 3.0 - center) >= 0.0);
+	}
+
+	// divergence theorem, the sphere is smoothed a bit but keeps its size
+	let volume = triangles(&mesh)
+		.map(|[a, b, c]| (a - center).dot((b - center).cross(c - center)) / 6.0)
+		.sum::<f32>();
+	let expected = 4.0 / 3.0 * std::f32::consts::PI * RADIUS.powi(3);
+	assert!(
+		(volume / expected - 1.0).abs() < 0.25,
+		"volume {volume}, expected about {expected}"
+	);
+}
+
+#[test]
+fn obj_export() {
+	let particles = sphere(Vector3::zero());
+	let mesh = Surface::default().reconstruct(particles.particles());
+	let mut bytes = Vec::new();
+	mesh.write_obj(&mut bytes).unwrap();
+	let text = String::from_utf8(bytes).unwrap();
+	let count = |prefix: &str| text.lines().filter(|l| l.starts_with(prefix)).count();
+	assert_eq!(count("v "), mesh.positions.len());
+	assert_eq!(count("vn "), mesh.normals.len());
+	assert_eq!(count("f "), mesh.indices.len() / 3);
+}
+
+#[test]
+fn no_particles_no_surface() {
+	assert!(Surface::default().reconstruct(&[]).indices.is_empty());
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use wgpu_fluid::sim::collider::{Collider, Shape};
+use wgpu_fluid::sim::force::{Field, ForceField};
+use wgpu_fluid::sim::particle::{Solver, SurfaceTension};
+use wgpu_fluid::sim::Particles;
+
+// a cube of 6^3 particles floating in zero gravity
+fn cube(surface_tension: SurfaceTension) -> Particles {
+	let mut scene = common::cube(30.0);
+	scene.material.surface_tension = surface_tension;
+	scene.solver.solver = Solver::Dfsph;
+	Particles::new(&scene)
+}
+
+// largest over rms distance from the center, 1.46 for the cube and 1.29 for a ball
+fn roundness(particles: &Particles) -> f32 {
+	let list = particles.particles();
+	let center = list.iter().map(|p| p.position).sum::<Vector3<f32>>() / list.len() as f32;
+	let distances = list
+		.iter()
+		.map(|p| (p.position - center).magnitude())
+		.collect::<Vec<_>>();
+	let max = distances.iter().copied().fold(0.0, f32::max);
+	let rms = (distances.iter().map(|r| r * r).sum::<f32>() / list.len() as f32).sqrt();
+	max / rms
+}
+
+fn relax(mut particles: Particles) -> f32 {
+	let before = roundness(&particles);
+	for _ in 0..300 {
+		particles.update();
+	}
+	let after = roundness(&particles);
+	let list = particles.particles();
+	// pulled together, nothing flew off
+	assert!(list.iter().all(|p| p.position.magnitude() < 50.0));
+	after / before
+}
+
+#[test]
+fn cube_stays_a_cube_without_surface_tension() {
+	assert!(relax(cube(SurfaceTension::None)) > 0.99);
+}
+
+#[test]
+fn akinci_cube_relaxes_toward_a_sphere() {
+	assert!(relax(cube(SurfaceTension::Akinci { coefficient: 0.05 })) < 0.93);
+}
+
+#[test]
+fn csf_cube_relaxes_toward_a_sphere() {
+	assert!(
+		relax(cube(SurfaceTension::Csf {
+			coefficient: 0.001,
+			threshold: 0.1,
+		})) < 0.93
+	);
+}
+
+// a single layer of fluid under a ceiling at y = 0, with gravity
+fn ceiling(adhesion: f32) -> f32 {
+	let mut scene = common::block(
+		Vector3::new(-30.0, -10.0, -30.0),
+		Vector3::new(30.0, 0.0, 30.0),
+	);
+	scene.colliders = vec![Collider::new(Shape::Plane {
+		normal: -Vector3::unit_y(),
+		offset: 0.0,
+	})];
+	scene.fields = vec![ForceField::new(Field::Gravity {
+		acceleration: Vector3::new(0.0, -0.02, 0.0),
+	})];
+	scene.material.adhesion = adhesion;
+	scene.solver.solver = Solver::Dfsph;
+	let mut particles = Particles::new(&scene);
+	for _ in 0..100 {
+		particles.update();
+	}
+	particles
+		.particles()
+		.iter()
+		.map(|p| p.position.y)
+		.fold(f32::INFINITY, f32::min)
+}
+
+#[test]
+fn adhesion_holds_fluid_to_a_ceiling() {
+	assert!(ceiling(0.0) < -20.0);
+	assert!(ceiling(0.1) > -10.0);
+}
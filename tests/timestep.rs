@@ -0,0 +1,77 @@
+mod common;
+
+use cgmath::Vector3;
+use wgpu_fluid::sim::timestep::TimeStep;
+use wgpu_fluid::sim::Particles;
+
+const H: f32 = 16.0;
+
+#[test]
+fn cfl_step_shrinks_as_speed_rises() {
+	let timestep = TimeStep::new(0.5);
+	let dt = |speed| timestep.stable_dt(H, speed, 0.0, 0.0);
+	// slow enough for the full frame
+	assert_eq!(dt(1.0), 0.5);
+	let steps = [10.0, 20.0, 40.0, 80.0].map(dt);
+	assert!(steps.windows(2).all(|w| w[1] < w[0]), "{steps:?}");
+	// dt = cfl h / v
+	assert!((dt(40.0) - 0.4 * H / 40.0).abs() < 1e-6);
+	// down to the minimum
+	assert_eq!(dt(1e6), timestep.min_dt);
+}
+
+#[test]
+fn force_and_viscosity_limit_the_step() {
+	let timestep = TimeStep::new(0.5);
+	// dt = force sqrt(h / a)
+	let dt = timestep.stable_dt(H, 0.0, 4.0, 0.0);
+	assert!((dt - 0.25 * (H / 4.0).sqrt()).abs() < 1e-6, "{dt}");
+	// dt = viscous h^2 / nu
+	let dt = timestep.stable_dt(H, 0.0, 0.0, 100.0);
+	assert!((dt - 0.125 * H * H / 100.0).abs() < 1e-6, "{dt}");
+	// the smallest limit wins
+	let dt = timestep.stable_dt(H, 40.0, 4.0, 100.0);
+	assert!((dt - 0.16).abs() < 1e-6, "{dt}");
+
+	let fixed = TimeStep {
+		adaptive: false,
+		..timestep
+	};
+	assert_eq!(fixed.stable_dt(H, 1e6, 1e6, 1e6), 0.5);
+}
+
+#[test]
+fn faster_fluid_takes_more_substeps() {
+	// a block moving as a whole, nothing but its speed sets the step
+	let step = |speed: f32| {
+		let mut particles = Particles::new(&common::cube(30.0));
+		for p in particles.particles_mut() {
+			p.velocity = Vector3::new(speed, 0.0, 0.0);
+		}
+		particles.update();
+		particles.stats()
+	};
+	let (slow, fast, faster) = (step(1.0), step(20.0), step(40.0));
+	assert_eq!((slow.substeps, slow.dt), (1, 0.5));
+	assert!(fast.dt < slow.dt && faster.dt < fast.dt);
+	assert!(faster.dt <= 0.4 * H / 40.0, "{faster:?}");
+	// the substeps add up to the frame
+	for stats in [fast, faster] {
+		assert!(
+			(stats.dt * stats.substeps as f32 - 0.5).abs() < 1e-5,
+			"{stats:?}"
+		);
+	}
+}
+
+#[test]
+fn substep_budget_drops_the_rest_of_the_frame() {
+	let mut particles = Particles::new(&common::cube(30.0));
+	particles.timestep_mut().max_substeps = 3;
+	for p in particles.particles_mut() {
+		p.velocity = Vector3::new(200.0, 0.0, 0.0);
+	}
+	particles.update();
+	assert_eq!(particles.stats().substeps, 3);
+	assert!(particles.time() < 0.5);
+}
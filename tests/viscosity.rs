@@ -0,0 +1,147 @@
+mod common;
+
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use common::checkerboard;
+use wgpu_fluid::sim::eos::EquationOfState;
+use wgpu_fluid::sim::particle::{Solver, VelocityParams, Viscosity};
+use wgpu_fluid::sim::scene::Fluid;
+use wgpu_fluid::sim::{Particles, Scene};
+
+const IMPLICIT: Viscosity = Viscosity::Implicit {
+	kinematic: 10.0,
+	tolerance: 1e-5,
+	max_iterations: 100,
+};
+
+// a 6^3 block in zero gravity without pressure, so only viscosity changes the velocities
+fn block(viscosity: Viscosity) -> Scene {
+	let mut scene = common::cube(30.0);
+	scene.material.viscosity = viscosity;
+	scene.solver.eos = EquationOfState::IdealGas { gas_const: 0.0 };
+	scene
+}
+
+// neighbors moving at 0 and 0.2 along x, returns what is left of the difference and the mean velocity
+fn smooth(scene: &Scene) -> (f32, Vector3<f32>) {
+	let mut particles = Particles::new(scene);
+	let signs = checkerboard(&particles);
+	for (p, sign) in particles.particles_mut().iter_mut().zip(&signs) {
+		p.velocity = Vector3::unit_x() * (0.1 + 0.1 * sign);
+	}
+	particles.update();
+	let list = particles.particles();
+	let amplitude = list
+		.iter()
+		.zip(&signs)
+		.map(|(p, sign)| (p.velocity.x - 0.1) * sign)
+		.sum::<f32>()
+		/ list.len() as f32;
+	let mean = list.iter().map(|p| p.velocity).sum::<Vector3<f32>>() / list.len() as f32;
+	(amplitude, mean)
+}
+
+#[test]
+fn uniform_motion_has_no_viscosity() {
+	for viscosity in Viscosity::ALL {
+		let mut particles = Particles::new(&block(viscosity));
+		for p in particles.particles_mut() {
+			p.velocity = Vector3::new(0.5, 0.0, 0.0);
+		}
+		particles.update();
+		for p in particles.particles() {
+			assert!((p.velocity - Vector3::new(0.5, 0.0, 0.0)).magnitude() < 1e-4);
+		}
+	}
+}
+
+#[test]
+fn models_damp_velocity_differences() {
+	let (none, _) = smooth(&block(Viscosity::Laplacian { dynamic: 0.0 }));
+	assert!((none - 0.1).abs() < 1e-5);
+
+	for viscosity in [
+		Viscosity::Laplacian { dynamic: 0.01 },
+		Viscosity::Morris { kinematic: 10.0 },
+		IMPLICIT,
+	] {
+		let (amplitude, mean) = smooth(&block(viscosity));
+		assert!(amplitude < 0.09, "{}: {amplitude}", viscosity.name());
+		// morris and the implicit solve are symmetric and keep the momentum
+		if viscosity.name() != "laplacian" {
+			assert!((mean - Vector3::new(0.1, 0.0, 0.0)).magnitude() < 1e-4);
+		}
+	}
+}
+
+#[test]
+fn artificial_viscosity_only_slows_approaching_particles() {
+	let pair = |speed: f32| {
+		let mut scene = block(Viscosity::Artificial {
+			alpha: 0.0,
+			beta: 1.0,
+		});
+		scene.fluids = vec![Fluid::Block {
+			min: Vector3::new(-10.0, 0.0, 0.0),
+			max: Vector3::new(10.0, 10.0, 10.0),
+			velocity: Vector3::zero(),
+		}];
+		let mut particles = Particles::new(&scene);
+		for p in particles.particles_mut() {
+			p.velocity = Vector3::unit_x() * -p.position.x.signum() * speed;
+		}
+		particles.update();
+		let list = particles.particles();
+		(list[1].velocity.x - list[0].velocity.x).abs()
+	};
+	// toward each other
+	assert!(pair(0.5) < 0.99);
+	// apart
+	assert!((pair(-0.5) - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn implicit_viscosity_is_stable_at_large_time_steps() {
+	// honey: explicit viscosity would need steps of 0.125 h^2 / nu = 0.0032
+	let thick = |viscosity| {
+		let mut scene = block(viscosity);
+		scene.solver.timestep.adaptive = false;
+		smooth(&scene).0
+	};
+	let explicit = thick(Viscosity::Morris {
+		kinematic: 10_000.0,
+	});
+	assert!(!explicit.is_finite() || explicit.abs() > 0.1);
+
+	let implicit = thick(Viscosity::Implicit {
+		kinematic: 10_000.0,
+		tolerance: 1e-5,
+		max_iterations: 200,
+	});
+	assert!(implicit.abs() < 0.01);
+}
+
+#[test]
+fn explicit_models_act_in_pbf() {
+	let pbf = |viscosity| {
+		let mut scene = block(viscosity);
+		scene.solver.solver = Solver::Pbf;
+		// PBF has no pressure to leave out, and artificial viscosity scales with the speed of sound
+		scene.solver.eos = EquationOfState::default();
+		// without XSPH, which smooths the velocities as well
+		scene.solver.velocity = Some(VelocityParams::default());
+		smooth(&scene).0
+	};
+	let none = pbf(Viscosity::Laplacian { dynamic: 0.0 });
+	for viscosity in [
+		Viscosity::Laplacian { dynamic: 0.01 },
+		Viscosity::Artificial {
+			alpha: 1.0,
+			beta: 0.0,
+		},
+		Viscosity::Morris { kinematic: 10.0 },
+	] {
+		let amplitude = pbf(viscosity);
+		assert!(amplitude < 0.9 * none, "{}: {amplitude}", viscosity.name());
+	}
+}
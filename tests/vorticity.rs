@@ -0,0 +1,91 @@
+mod common;
+
+use cgmath::Vector3;
+use wgpu_fluid::sim::particle::VelocityParams;
+use wgpu_fluid::sim::scene::SolverSettings;
+use wgpu_fluid::sim::{Attribute, Particles, Scene};
+
+// a 60^3 block spinning about the y axis at `spin` radians per time, in zero gravity
+fn spinning(spin: f32, velocity: VelocityParams) -> Particles {
+	let mut scene = common::cube(30.0);
+	scene.solver.velocity = Some(velocity);
+	let mut particles = Particles::new(&scene);
+	for p in particles.particles_mut() {
+		p.velocity = Vector3::unit_y().cross(p.position) * spin;
+	}
+	particles
+}
+
+fn mean(values: &[f32]) -> f32 {
+	values.iter().sum::<f32>() / values.len() as f32
+}
+
+#[test]
+fn confinement_keeps_more_rotation() {
+	let vorticity = |confinement: f32| {
+		let mut particles = spinning(
+			0.01,
+			VelocityParams {
+				vorticity: confinement,
+				..VelocityParams::default()
+			},
+		);
+		for _ in 0..50 {
+			particles.update();
+		}
+		mean(&particles.attribute(Attribute::Vorticity))
+	};
+	assert!(vorticity(0.001) > vorticity(0.0));
+}
+
+#[test]
+fn xsph_smooths_velocities() {
+	let roughness = |xsph: f32| {
+		let mut particles = spinning(
+			0.0,
+			VelocityParams {
+				xsph,
+				..VelocityParams::default()
+			},
+		);
+		// neighbors move in opposite directions
+		let signs = common::checkerboard(&particles);
+		for (p, sign) in particles.particles_mut().iter_mut().zip(&signs) {
+			p.velocity = Vector3::unit_x() * (0.1 * sign);
+		}
+		particles.update();
+		// what is left of the checkerboard
+		let amplitude = particles
+			.particles()
+			.iter()
+			.zip(&signs)
+			.map(|(p, sign)| p.velocity.x * sign)
+			.collect::<Vec<_>>();
+		mean(&amplitude)
+	};
+	assert!(roughness(0.5) < 0.6 * roughness(0.0));
+}
+
+#[test]
+fn pbf_keeps_its_velocity_passes_unless_set() {
+	let solver = |text: &str| ron::from_str::<SolverSettings>(text).unwrap();
+	let velocity = |settings: SolverSettings| {
+		let scene = Scene {
+			fluids: Vec::new(),
+			solver: settings,
+			..Scene::default()
+		};
+		Particles::new(&scene).velocity_params()
+	};
+
+	let pbf = velocity(solver("(solver: Pbf)"));
+	assert_eq!((pbf.vorticity, pbf.xsph), (0.0001, 0.01));
+	let other = velocity(solver("(solver: Dfsph)"));
+	assert_eq!((other.vorticity, other.xsph), (0.0, 0.0));
+	let set = velocity(solver("(solver: Pbf, velocity: Some((xsph: 0.1)))"));
+	assert_eq!((set.vorticity, set.xsph), (0.0, 0.1));
+
+	// these moved to `velocity`
+	assert!(ron::from_str::<SolverSettings>("(pbf: (xsph: 0.01))").is_err());
+	assert!(ron::from_str::<SolverSettings>("(pbf: (vorticity: 0.0001))").is_err());
+}